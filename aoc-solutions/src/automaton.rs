@@ -0,0 +1,526 @@
+//! A generic engine for synchronous cellular automata on 2D grids.
+
+use ndarray::Array2;
+
+use crate::cycle::{self, Cycle};
+
+const MOORE_OFFSETS: [(isize, isize); 9] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 0),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+const VON_NEUMANN_OFFSETS: [(isize, isize); 5] = [(-1, 0), (0, -1), (0, 0), (0, 1), (1, 0)];
+
+#[inline]
+fn checked_add(a: usize, b: isize) -> Option<usize> {
+    if b >= 0 {
+        a.checked_add(b as usize)
+    } else {
+        a.checked_sub((-b) as usize)
+    }
+}
+
+#[inline]
+fn offset_index(
+    grid_dim: (usize, usize),
+    index: (usize, usize),
+    (d_row, d_col): (isize, isize),
+) -> Option<(usize, usize)> {
+    let row = checked_add(index.0, d_row).filter(|&r| r < grid_dim.0)?;
+    let col = checked_add(index.1, d_col).filter(|&c| c < grid_dim.1)?;
+    Some((row, col))
+}
+
+/// Determines which cells' states are passed to a [`Rule`] when updating a cell.
+pub trait Neighborhood<C> {
+    /// The number of neighbor states pushed by [`Neighborhood::collect_neighbors`].
+    fn size(&self) -> usize;
+
+    /// Pushes the states of the neighbors of the cell at `index` onto `neighbors`, in an order
+    /// that is the same for every cell.
+    ///
+    /// Positions beyond the edge of the grid have the state `outside`.
+    fn collect_neighbors(
+        &self,
+        grid: &Array2<C>,
+        index: (usize, usize),
+        outside: C,
+        neighbors: &mut Vec<C>,
+    );
+}
+
+/// Collects the neighbors at the given offsets, skipping the cell itself unless `include_center`.
+fn collect_at_offsets<C: Copy>(
+    offsets: &[(isize, isize)],
+    include_center: bool,
+    grid: &Array2<C>,
+    index: (usize, usize),
+    outside: C,
+    neighbors: &mut Vec<C>,
+) {
+    let grid_dim = grid.dim();
+    neighbors.extend(
+        offsets
+            .iter()
+            .filter(|&&offset| include_center || offset != (0, 0))
+            .map(|&offset| offset_index(grid_dim, index, offset).map_or(outside, |i| grid[i])),
+    );
+}
+
+/// The eight cells surrounding a cell, in row-major order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Moore {
+    /// Whether the cell itself should be included (in the middle), making up a 3x3 window.
+    pub include_center: bool,
+}
+
+impl<C: Copy> Neighborhood<C> for Moore {
+    fn size(&self) -> usize {
+        if self.include_center {
+            9
+        } else {
+            8
+        }
+    }
+
+    fn collect_neighbors(
+        &self,
+        grid: &Array2<C>,
+        index: (usize, usize),
+        outside: C,
+        neighbors: &mut Vec<C>,
+    ) {
+        collect_at_offsets(
+            &MOORE_OFFSETS,
+            self.include_center,
+            grid,
+            index,
+            outside,
+            neighbors,
+        );
+    }
+}
+
+/// The four cells orthogonally adjacent to a cell, in row-major order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VonNeumann {
+    /// Whether the cell itself should be included (in the middle).
+    pub include_center: bool,
+}
+
+impl<C: Copy> Neighborhood<C> for VonNeumann {
+    fn size(&self) -> usize {
+        if self.include_center {
+            5
+        } else {
+            4
+        }
+    }
+
+    fn collect_neighbors(
+        &self,
+        grid: &Array2<C>,
+        index: (usize, usize),
+        outside: C,
+        neighbors: &mut Vec<C>,
+    ) {
+        collect_at_offsets(
+            &VON_NEUMANN_OFFSETS,
+            self.include_center,
+            grid,
+            index,
+            outside,
+            neighbors,
+        );
+    }
+}
+
+/// The first cell in each of the eight Moore directions that is not transparent, or the outside
+/// state if the line of sight leaves the grid.
+#[derive(Debug, Clone, Copy)]
+pub struct LineOfSight<F> {
+    transparent: F,
+}
+
+impl<F> LineOfSight<F> {
+    pub const fn new(transparent: F) -> Self {
+        Self { transparent }
+    }
+}
+
+impl<C: Copy, F: Fn(C) -> bool> Neighborhood<C> for LineOfSight<F> {
+    fn size(&self) -> usize {
+        8
+    }
+
+    fn collect_neighbors(
+        &self,
+        grid: &Array2<C>,
+        index: (usize, usize),
+        outside: C,
+        neighbors: &mut Vec<C>,
+    ) {
+        let grid_dim = grid.dim();
+        neighbors.extend(
+            MOORE_OFFSETS
+                .iter()
+                .filter(|&&offset| offset != (0, 0))
+                .map(|&offset| {
+                    let mut current = index;
+                    loop {
+                        match offset_index(grid_dim, current, offset) {
+                            Some(next) if (self.transparent)(grid[next]) => current = next,
+                            Some(next) => break grid[next],
+                            None => break outside,
+                        }
+                    }
+                }),
+        );
+    }
+}
+
+/// Computes the next state of a cell from its current state and the states of its neighbors.
+pub trait Rule<C> {
+    fn apply(&self, cell: C, neighbors: &[C]) -> C;
+}
+
+impl<C, F: Fn(C, &[C]) -> C> Rule<C> for F {
+    fn apply(&self, cell: C, neighbors: &[C]) -> C {
+        self(cell, neighbors)
+    }
+}
+
+/// How cells beyond the edges of the grid are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Boundary<C> {
+    /// All cells beyond the grid permanently have the given state.
+    Fixed(C),
+    /// The grid is embedded in an infinite background of cells which all share the given state.
+    /// The background evolves by the same rule as the cells of the grid.
+    Infinite(C),
+}
+
+impl<C: Copy> Boundary<C> {
+    pub fn state(&self) -> C {
+        match *self {
+            Boundary::Fixed(state) | Boundary::Infinite(state) => state,
+        }
+    }
+}
+
+/// A synchronously updated cellular automaton.
+///
+/// Two grids are kept and swapped after each step, so stepping does not allocate.
+#[derive(Debug, Clone)]
+pub struct Automaton<C, N, R> {
+    cells: Array2<C>,
+    next_cells: Array2<C>,
+    neighbors: Vec<C>,
+    neighborhood: N,
+    rule: R,
+    boundary: Boundary<C>,
+    generation: usize,
+}
+
+impl<C, N, R> Automaton<C, N, R>
+where
+    C: Copy + PartialEq,
+    N: Neighborhood<C>,
+    R: Rule<C>,
+{
+    pub fn new(cells: Array2<C>, neighborhood: N, rule: R, boundary: Boundary<C>) -> Self {
+        Self {
+            next_cells: cells.clone(),
+            neighbors: Vec::with_capacity(neighborhood.size()),
+            cells,
+            neighborhood,
+            rule,
+            boundary,
+            generation: 0,
+        }
+    }
+
+    pub fn cells(&self) -> &Array2<C> {
+        &self.cells
+    }
+
+    /// Gives mutable access to the cells, e.g. for updates that are not local to a cell.
+    pub fn cells_mut(&mut self) -> &mut Array2<C> {
+        &mut self.cells
+    }
+
+    pub fn boundary(&self) -> Boundary<C> {
+        self.boundary
+    }
+
+    /// The number of steps taken so far, saturating at `usize::MAX`.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Extends the grid by `margin` cells on every side, filled with the boundary state.
+    ///
+    /// With an infinite boundary, padding by at least the number of steps that will be taken
+    /// ensures that no part of the pattern is cut off at the edge of the grid.
+    pub fn pad(&mut self, margin: usize) {
+        let (rows, cols) = self.cells.dim();
+        let mut padded = Array2::from_elem(
+            (rows + 2 * margin, cols + 2 * margin),
+            self.boundary.state(),
+        );
+        padded
+            .slice_mut(ndarray::s![margin..margin + rows, margin..margin + cols])
+            .assign(&self.cells);
+        self.next_cells = padded.clone();
+        self.cells = padded;
+    }
+
+    /// Updates all cells (and the background of an infinite boundary) at once.
+    ///
+    /// Returns whether any state changed, i.e. `false` once a fixed point has been reached.
+    pub fn step(&mut self) -> bool {
+        let Self {
+            cells,
+            next_cells,
+            neighbors,
+            neighborhood,
+            rule,
+            boundary,
+            generation,
+        } = self;
+
+        let outside = boundary.state();
+        let mut changed = false;
+        for (index, next_cell) in next_cells.indexed_iter_mut() {
+            neighbors.clear();
+            neighborhood.collect_neighbors(cells, index, outside, neighbors);
+            let cell = cells[index];
+            *next_cell = rule.apply(cell, neighbors);
+            changed |= *next_cell != cell;
+        }
+
+        if let Boundary::Infinite(background) = boundary {
+            neighbors.clear();
+            neighbors.resize(neighborhood.size(), *background);
+            let next_background = rule.apply(*background, neighbors);
+            changed |= next_background != *background;
+            *background = next_background;
+        }
+
+        std::mem::swap(cells, next_cells);
+        *generation = generation.saturating_add(1);
+        changed
+    }
+
+    /// Steps until a fixed point is reached and returns the number of steps that changed a state.
+    ///
+    /// This never returns for automata that do not settle, see [`Automaton::find_cycle`].
+    pub fn run_until_stable(&mut self) -> usize {
+        let mut changing_steps = 0;
+        while self.step() {
+            changing_steps += 1;
+        }
+        changing_steps
+    }
+
+    /// Whether all cells and the boundary have the same states in both automata.
+    fn same_state(&self, other: &Self) -> bool {
+        self.cells == other.cells && self.boundary == other.boundary
+    }
+
+    /// Finds the cycle the automaton runs into from its current generation.
+    ///
+    /// This never returns for automata whose states never repeat, e.g. growing patterns in an
+    /// infinite background.
+    pub fn find_cycle(&self) -> Cycle
+    where
+        Self: Clone,
+    {
+        cycle::find_cycle_by(
+            self,
+            |automaton| {
+                automaton.step();
+            },
+            Self::same_state,
+        )
+    }
+
+    /// Advances the automaton by `steps` generations, skipping over whole periods once the
+    /// automaton has run into a cycle.
+    ///
+    /// The states are exact for any number of steps, but the generation saturates at
+    /// `usize::MAX` if it would overflow.
+    pub fn fast_forward(&mut self, steps: usize)
+    where
+        Self: Clone,
+    {
+        let generation = self.generation;
+        *self = cycle::fast_forward_by(
+            self,
+            |automaton| {
+                automaton.step();
+            },
+            Self::same_state,
+            steps,
+        );
+        self.generation = generation.saturating_add(steps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::array;
+
+    fn game_of_life(alive: bool, neighbors: &[bool]) -> bool {
+        matches!(
+            (alive, neighbors.iter().filter(|&&n| n).count()),
+            (true, 2 | 3) | (false, 3)
+        )
+    }
+
+    #[test]
+    fn test_blinker_cycle() {
+        let automaton = Automaton::new(
+            array![
+                [false, false, false],
+                [true, true, true],
+                [false, false, false]
+            ],
+            Moore::default(),
+            game_of_life,
+            Boundary::Fixed(false),
+        );
+        assert_eq!(
+            automaton.find_cycle(),
+            Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_block_fixed_point() {
+        let mut automaton = Automaton::new(
+            array![
+                [false, false, false, false],
+                [false, true, true, false],
+                [false, true, true, false],
+                [false, false, false, false]
+            ],
+            Moore::default(),
+            game_of_life,
+            Boundary::Fixed(false),
+        );
+        assert_eq!(automaton.run_until_stable(), 0);
+        assert_eq!(
+            automaton.find_cycle(),
+            Cycle {
+                start: 0,
+                period: 1
+            }
+        );
+    }
+
+    /// A Game of Life with a dead boundary, where `#` marks the live cells.
+    fn life(rows: &[&str]) -> Automaton<bool, Moore, fn(bool, &[bool]) -> bool> {
+        let cells = Array2::from_shape_fn((rows.len(), rows[0].len()), |(row, column)| {
+            rows[row].as_bytes()[column] == b'#'
+        });
+        Automaton::new(
+            cells,
+            Moore::default(),
+            game_of_life,
+            Boundary::Fixed(false),
+        )
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        // Well-known Game of Life patterns: the blinker oscillates from the start, the L-tromino
+        // becomes a block after one step and the T-tetromino becomes a traffic light, four
+        // blinkers, after nine steps.
+        let patterns = [
+            (life(&["...", "###", "..."]), 0, 2),
+            (life(&["....", ".#..", ".##.", "...."]), 1, 1),
+            (
+                life(&[
+                    "...........",
+                    "...........",
+                    "...........",
+                    "...........",
+                    "....###....",
+                    ".....#.....",
+                    "...........",
+                    "...........",
+                    "...........",
+                    "...........",
+                    "...........",
+                ]),
+                9,
+                2,
+            ),
+        ];
+
+        for (automaton, start, period) in patterns {
+            assert_eq!(automaton.find_cycle(), Cycle { start, period });
+            let huge_steps = [1_000_000_000_000_000_001, usize::MAX];
+            for steps in (0..=20).chain(huge_steps) {
+                let mut fast_forwarded = automaton.clone();
+                fast_forwarded.fast_forward(steps);
+
+                let reduced_steps = if steps < start {
+                    steps
+                } else {
+                    start + (steps - start) % period
+                };
+                let mut simulated = automaton.clone();
+                for _ in 0..reduced_steps {
+                    simulated.step();
+                }
+
+                assert_eq!(fast_forwarded.cells(), simulated.cells());
+                assert_eq!(fast_forwarded.generation(), steps);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fast_forward_after_steps() {
+        let blinker = life(&["...", "###", "..."]);
+        let mut automaton = blinker.clone();
+        automaton.step();
+        automaton.fast_forward(4);
+        assert_eq!(automaton.generation(), 5);
+        assert_eq!(automaton.cells(), life(&[".#.", ".#.", ".#."]).cells());
+
+        // 5 + usize::MAX steps are an even number, so the blinker is back in its first phase.
+        automaton.fast_forward(usize::MAX);
+        assert_eq!(automaton.generation(), usize::MAX);
+        assert_eq!(automaton.cells(), blinker.cells());
+        automaton.step();
+        assert_eq!(automaton.generation(), usize::MAX);
+    }
+
+    #[test]
+    fn test_infinite_background() {
+        let mut automaton = Automaton::new(
+            array![[false]],
+            VonNeumann::default(),
+            |cell: bool, _: &[bool]| !cell,
+            Boundary::Infinite(false),
+        );
+        automaton.step();
+        assert_eq!(automaton.cells(), array![[true]]);
+        assert_eq!(automaton.boundary(), Boundary::Infinite(true));
+    }
+}
@@ -0,0 +1,312 @@
+//! Checking, scoring and repairing lines of nested brackets.
+
+use std::fmt::{self, Display};
+
+/// A pair of matching brackets together with the scores of its closing bracket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BracketPair {
+    pub opening: char,
+    pub closing: char,
+    /// The score of a line that is corrupted by this closing bracket.
+    pub syntax_error_score: u64,
+    /// The value of this closing bracket as a digit of a completion score.
+    pub completion_score: u64,
+}
+
+/// What is wrong with a line, if anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// All brackets are properly nested and closed.
+    Valid,
+    /// A closing bracket does not match the innermost open bracket.
+    Corrupted {
+        /// The index of the offending character in the line.
+        column: usize,
+        /// The closing bracket that would have been legal, `None` if no bracket was open.
+        expected: Option<char>,
+        found: char,
+    },
+    /// The character is not a bracket of the language.
+    UnknownCharacter { column: usize, found: char },
+    /// Some brackets are still open at the end of the line.
+    Incomplete {
+        /// The closing brackets that need to be appended to complete the line.
+        completion: String,
+    },
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnosis::Valid => write!(f, "valid"),
+            Diagnosis::Corrupted {
+                column,
+                expected: Some(expected),
+                found,
+            } => write!(
+                f,
+                "corrupted at column {column}: expected '{expected}', but found '{found}' instead"
+            ),
+            Diagnosis::Corrupted {
+                column,
+                expected: None,
+                found,
+            } => write!(
+                f,
+                "corrupted at column {column}: found '{found}' without an open bracket"
+            ),
+            Diagnosis::UnknownCharacter { column, found } => {
+                write!(f, "unknown character '{found}' at column {column}")
+            }
+            Diagnosis::Incomplete { completion } => {
+                write!(f, "incomplete, complete by adding \"{completion}\"")
+            }
+        }
+    }
+}
+
+/// The completion score of a line does not fit into a `u64`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionScoreOverflow {
+    /// The closing brackets whose score overflows.
+    pub completion: String,
+}
+
+impl Display for CompletionScoreOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The completion score of \"{}\" does not fit into 64 bits",
+            self.completion
+        )
+    }
+}
+
+impl std::error::Error for CompletionScoreOverflow {}
+
+/// A set of bracket pairs, with the scoring rules of the navigation subsystem by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketLanguage {
+    pairs: Vec<BracketPair>,
+    /// The base in which the completion scores of the closing brackets are read as digits.
+    completion_base: u64,
+}
+
+impl Default for BracketLanguage {
+    fn default() -> Self {
+        Self::new(
+            vec![
+                BracketPair {
+                    opening: '(',
+                    closing: ')',
+                    syntax_error_score: 3,
+                    completion_score: 1,
+                },
+                BracketPair {
+                    opening: '[',
+                    closing: ']',
+                    syntax_error_score: 57,
+                    completion_score: 2,
+                },
+                BracketPair {
+                    opening: '{',
+                    closing: '}',
+                    syntax_error_score: 1197,
+                    completion_score: 3,
+                },
+                BracketPair {
+                    opening: '<',
+                    closing: '>',
+                    syntax_error_score: 25137,
+                    completion_score: 4,
+                },
+            ],
+            5,
+        )
+    }
+}
+
+impl BracketLanguage {
+    pub fn new(pairs: Vec<BracketPair>, completion_base: u64) -> Self {
+        Self {
+            pairs,
+            completion_base,
+        }
+    }
+
+    fn pair_opened_by(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.opening == c)
+    }
+
+    fn pair_closed_by(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.closing == c)
+    }
+
+    /// Checks the line up to its first error.
+    pub fn diagnose(&self, line: &str) -> Diagnosis {
+        let mut open_pairs = Vec::new();
+        for (column, c) in line.chars().enumerate() {
+            if let Some(pair) = self.pair_opened_by(c) {
+                open_pairs.push(pair);
+            } else if let Some(pair) = self.pair_closed_by(c) {
+                match open_pairs.pop() {
+                    Some(innermost_pair) if innermost_pair == pair => (),
+                    innermost_pair => {
+                        return Diagnosis::Corrupted {
+                            column,
+                            expected: innermost_pair.map(|pair| pair.closing),
+                            found: c,
+                        }
+                    }
+                }
+            } else {
+                return Diagnosis::UnknownCharacter { column, found: c };
+            }
+        }
+
+        if open_pairs.is_empty() {
+            Diagnosis::Valid
+        } else {
+            Diagnosis::Incomplete {
+                completion: open_pairs.iter().rev().map(|pair| pair.closing).collect(),
+            }
+        }
+    }
+
+    /// The syntax error score of a corrupted line, `None` for other diagnoses.
+    pub fn syntax_error_score(&self, diagnosis: &Diagnosis) -> Option<u64> {
+        match diagnosis {
+            Diagnosis::Corrupted { found, .. } => self
+                .pair_closed_by(*found)
+                .map(|pair| pair.syntax_error_score),
+            _ => None,
+        }
+    }
+
+    /// The completion score of an incomplete line, `None` for other diagnoses.
+    ///
+    /// Panics if the completion contains a character that is not a closing bracket of the
+    /// language, which `diagnose` never produces.
+    pub fn completion_score(
+        &self,
+        diagnosis: &Diagnosis,
+    ) -> Option<Result<u64, CompletionScoreOverflow>> {
+        match diagnosis {
+            Diagnosis::Incomplete { completion } => Some(
+                completion
+                    .chars()
+                    .try_fold(0u64, |score, c| {
+                        let pair = self
+                            .pair_closed_by(c)
+                            .expect("A completion must consist of closing brackets");
+                        score
+                            .checked_mul(self.completion_base)?
+                            .checked_add(pair.completion_score)
+                    })
+                    .ok_or_else(|| CompletionScoreOverflow {
+                        completion: completion.clone(),
+                    }),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Fixes all errors of the line: mismatched closing brackets are replaced by the expected
+    /// ones, closing brackets without an open bracket and unknown characters are removed, and
+    /// brackets still open at the end are closed.
+    pub fn repair(&self, line: &str) -> String {
+        let mut repaired = String::with_capacity(line.len());
+        let mut open_pairs = Vec::new();
+        for c in line.chars() {
+            if let Some(pair) = self.pair_opened_by(c) {
+                open_pairs.push(pair);
+                repaired.push(c);
+            } else if self.pair_closed_by(c).is_some() {
+                if let Some(innermost_pair) = open_pairs.pop() {
+                    repaired.push(innermost_pair.closing);
+                }
+            }
+        }
+        repaired.extend(open_pairs.iter().rev().map(|pair| pair.closing));
+        repaired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnose() {
+        let language = BracketLanguage::default();
+
+        let corrupted = language.diagnose("{([(<{}[<>[]}>{[]{[(<()>");
+        assert_eq!(
+            corrupted,
+            Diagnosis::Corrupted {
+                column: 12,
+                expected: Some(']'),
+                found: '}'
+            }
+        );
+        assert_eq!(language.syntax_error_score(&corrupted), Some(1197));
+
+        let incomplete = language.diagnose("[({(<(())[]>[[{[]{<()<>>");
+        assert_eq!(
+            incomplete,
+            Diagnosis::Incomplete {
+                completion: String::from("}}]])})]")
+            }
+        );
+        assert_eq!(language.completion_score(&incomplete), Some(Ok(288957)));
+
+        // 5^28 > 2^64, so 28 open brackets are too many.
+        let overflowing = language.diagnose(&"<".repeat(28));
+        assert_eq!(
+            language.completion_score(&overflowing),
+            Some(Err(CompletionScoreOverflow {
+                completion: ">".repeat(28)
+            }))
+        );
+        assert!(matches!(
+            language.completion_score(&language.diagnose(&"<".repeat(27))),
+            Some(Ok(_))
+        ));
+
+        assert_eq!(language.diagnose("[<>({}){}[([])<>]]"), Diagnosis::Valid);
+        assert_eq!(
+            language.diagnose("(]"),
+            Diagnosis::Corrupted {
+                column: 1,
+                expected: Some(')'),
+                found: ']'
+            }
+        );
+    }
+
+    #[test]
+    fn test_custom_language_and_repair() {
+        let language = BracketLanguage::new(
+            vec![BracketPair {
+                opening: '«',
+                closing: '»',
+                syntax_error_score: 1,
+                completion_score: 1,
+            }],
+            2,
+        );
+        assert_eq!(
+            language.diagnose("««»x"),
+            Diagnosis::UnknownCharacter {
+                column: 3,
+                found: 'x'
+            }
+        );
+        assert_eq!(language.repair("»««»x"), "««»»");
+
+        let language = BracketLanguage::default();
+        let repaired = language.repair("{([(<{}[<>[]}>{[]{[(<()>");
+        assert_eq!(repaired, "{([(<{}[<>[]]>{[]{[(<()>)]}})])}");
+        assert_eq!(language.diagnose(&repaired), Diagnosis::Valid);
+    }
+}
@@ -0,0 +1,189 @@
+//! Cycle detection and fast-forwarding for deterministic simulations.
+//!
+//! Cycles are found with Brent's algorithm, which only needs to keep two states around and clones
+//! a state only a logarithmic number of times.
+
+/// A cycle in the sequence of states of a deterministic simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// The number of steps after which the simulation first reaches a state that is part of the
+    /// cycle.
+    pub start: usize,
+    /// The number of steps after which the states repeat, 1 for a fixed point.
+    pub period: usize,
+}
+
+/// Finds the period of the cycle reached from `initial` (first phase of Brent's algorithm).
+///
+/// Gives up and returns the state after `limit` steps if no cycle is found by then.
+fn find_period<S, F, E>(initial: &S, step: &mut F, eq: &E, limit: usize) -> Result<usize, S>
+where
+    S: Clone,
+    F: FnMut(&mut S),
+    E: Fn(&S, &S) -> bool,
+{
+    let mut hare = initial.clone();
+    if limit == 0 {
+        return Err(hare);
+    }
+    let mut tortoise = initial.clone();
+    step(&mut hare);
+    let mut hare_steps = 1;
+
+    let mut power = 1;
+    let mut period = 1;
+    while !eq(&tortoise, &hare) {
+        if hare_steps == limit {
+            return Err(hare);
+        }
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        step(&mut hare);
+        hare_steps += 1;
+        period += 1;
+    }
+
+    Ok(period)
+}
+
+/// Finds the cycle reached from `initial` together with the first state that is part of it, or
+/// returns the state after `limit` steps if no cycle is found by then.
+fn find_cycle_within<S, F, E>(
+    initial: &S,
+    step: &mut F,
+    eq: &E,
+    limit: usize,
+) -> Result<(Cycle, S), S>
+where
+    S: Clone,
+    F: FnMut(&mut S),
+    E: Fn(&S, &S) -> bool,
+{
+    let period = find_period(initial, step, eq, limit)?;
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    for _ in 0..period {
+        step(&mut hare);
+    }
+    let mut start = 0;
+    while !eq(&tortoise, &hare) {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+
+    Ok((Cycle { start, period }, tortoise))
+}
+
+/// Finds the cycle that repeatedly applying `step` to `initial` runs into, comparing states with
+/// `eq`.
+///
+/// This never returns if the states never repeat.
+pub fn find_cycle_by<S, F, E>(initial: &S, mut step: F, eq: E) -> Cycle
+where
+    S: Clone,
+    F: FnMut(&mut S),
+    E: Fn(&S, &S) -> bool,
+{
+    match find_cycle_within(initial, &mut step, &eq, usize::MAX) {
+        Ok((cycle, _)) => cycle,
+        Err(_) => panic!("No cycle found within {} steps", usize::MAX),
+    }
+}
+
+/// Like [`find_cycle_by`], comparing states with [`PartialEq`].
+pub fn find_cycle<S, F>(initial: &S, step: F) -> Cycle
+where
+    S: Clone + PartialEq,
+    F: FnMut(&mut S),
+{
+    find_cycle_by(initial, step, S::eq)
+}
+
+/// Computes the state after applying `step` to `initial` `n` times, comparing states with `eq`.
+///
+/// If the states run into a cycle, only the steps up to the detection of the cycle and those
+/// within the last partial period are simulated, so `n` may be arbitrarily large. Otherwise at
+/// most `n` steps are simulated.
+pub fn fast_forward_by<S, F, E>(initial: &S, mut step: F, eq: E, n: usize) -> S
+where
+    S: Clone,
+    F: FnMut(&mut S),
+    E: Fn(&S, &S) -> bool,
+{
+    match find_cycle_within(initial, &mut step, &eq, n) {
+        Ok((Cycle { start, period }, mut state)) if n >= start => {
+            for _ in 0..(n - start) % period {
+                step(&mut state);
+            }
+            state
+        }
+        Ok(_) => {
+            let mut state = initial.clone();
+            for _ in 0..n {
+                step(&mut state);
+            }
+            state
+        }
+        Err(state_after_n) => state_after_n,
+    }
+}
+
+/// Like [`fast_forward_by`], comparing states with [`PartialEq`].
+pub fn fast_forward<S, F>(initial: &S, step: F, n: usize) -> S
+where
+    S: Clone + PartialEq,
+    F: FnMut(&mut S),
+{
+    fast_forward_by(initial, step, S::eq, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(x: &mut u32) {
+        *x = (*x * *x + 1) % 255;
+    }
+
+    fn naive_nth(mut x: u32, n: usize) -> u32 {
+        for _ in 0..n {
+            step(&mut x);
+        }
+        x
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        // 3 -> 10 -> 101 -> 2 -> 5 -> 26 -> 167 -> 95 -> 101
+        assert_eq!(
+            find_cycle(&3, step),
+            Cycle {
+                start: 2,
+                period: 6
+            }
+        );
+        assert_eq!(
+            find_cycle(&0, |x: &mut u32| *x = 7),
+            Cycle {
+                start: 1,
+                period: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        for n in 0..20 {
+            assert_eq!(fast_forward(&3, step, n), naive_nth(3, n));
+        }
+        assert_eq!(
+            fast_forward(&3, step, 1_000_000_000_000),
+            naive_nth(3, 2 + (1_000_000_000_000 - 2) % 6)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Sets of the lowercase letters `a` to `z`, e.g. the questions answered on a customs declaration
+//! form or the segments lit on a display.
+
+use std::fmt::{self, Display};
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+use std::str::FromStr;
+
+/// A set of lowercase letters, stored as a bit set with bit `i` for the letter `'a' + i`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LetterSet(u32);
+
+impl LetterSet {
+    /// The number of letters that a set can hold.
+    pub const CAPACITY: usize = 26;
+    pub const EMPTY: Self = Self(0);
+    pub const ALL: Self = Self((1 << Self::CAPACITY) - 1);
+
+    /// Creates a set from its bits, `None` if bits above the letter `z` are set.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        (bits & !Self::ALL.0 == 0).then_some(Self(bits))
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// The set containing only the letter, `None` if it is not a lowercase letter.
+    pub fn single(letter: char) -> Option<Self> {
+        letter
+            .is_ascii_lowercase()
+            .then(|| Self(1 << (letter as u32 - 'a' as u32)))
+    }
+
+    pub fn contains(self, letter: char) -> bool {
+        matches!(Self::single(letter), Some(single) if self.is_superset(single))
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn symmetric_difference(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn is_subset(self, other: Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn is_superset(self, other: Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// The letters of the set in alphabetical order.
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        ('a'..='z').filter(move |&letter| self.contains(letter))
+    }
+}
+
+impl BitOr for LetterSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for LetterSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+impl BitAnd for LetterSet {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl BitAndAssign for LetterSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(rhs);
+    }
+}
+
+impl BitXor for LetterSet {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl BitXorAssign for LetterSet {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = self.symmetric_difference(rhs);
+    }
+}
+
+impl FromIterator<LetterSet> for LetterSet {
+    /// Unites the sets.
+    fn from_iter<I: IntoIterator<Item = LetterSet>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::EMPTY, Self::union)
+    }
+}
+
+impl FromStr for LetterSet {
+    type Err = &'static str;
+
+    /// Parses the letters of the set in any order, where repeated letters are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .map(|letter| Self::single(letter).ok_or("only lowercase letters can be in a set"))
+            .collect()
+    }
+}
+
+impl Display for LetterSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.iter().try_for_each(|letter| write!(f, "{letter}"))
+    }
+}
+
+/// The letters contained in at least `k` of the sets, e.g. the questions that at least `k` people
+/// of a group answered with yes.
+pub fn in_at_least<I: IntoIterator<Item = LetterSet>>(sets: I, k: usize) -> LetterSet {
+    let mut counts = [0usize; LetterSet::CAPACITY];
+    for set in sets {
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count += (set.0 >> i & 1) as usize;
+        }
+    }
+
+    (0..LetterSet::CAPACITY)
+        .filter(|&i| counts[i] >= k)
+        .fold(LetterSet::EMPTY, |set, i| LetterSet(set.0 | 1 << i))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_operations() {
+        let abc: LetterSet = "cab".parse().unwrap();
+        let bcd: LetterSet = "bcdd".parse().unwrap();
+        assert_eq!(abc.to_string(), "abc");
+        assert_eq!((abc | bcd).to_string(), "abcd");
+        assert_eq!((abc & bcd).to_string(), "bc");
+        assert_eq!((abc ^ bcd).to_string(), "ad");
+        assert_eq!(abc.difference(bcd).len(), 1);
+        assert!(abc.contains('a') && !abc.contains('d') && !abc.contains('A'));
+        assert!("bc".parse::<LetterSet>().unwrap().is_subset(abc));
+        assert!("aB".parse::<LetterSet>().is_err());
+        assert_eq!(LetterSet::from_bits(1 << 26), None);
+
+        let sets = [abc, bcd, "z".parse().unwrap()];
+        assert_eq!(in_at_least(sets, 0), LetterSet::ALL);
+        assert_eq!(in_at_least(sets, 1).to_string(), "abcdz");
+        assert_eq!(in_at_least(sets, 2).to_string(), "bc");
+        assert!(in_at_least(sets, 3).is_empty());
+    }
+}
@@ -2,6 +2,17 @@
 
 use paste::paste;
 
+pub mod automaton;
+pub mod brackets;
+pub mod cycle;
+pub mod letters;
+pub mod linalg;
+pub mod ocr;
+pub mod params;
+pub mod render;
+pub mod report;
+pub mod sums;
+pub mod windows;
 pub mod year2020;
 pub mod year2021;
 
@@ -38,3 +49,91 @@ gen_solve_puzzle_fn!(
         [01, 02, 03, 04, 05, 06, 07, 08, 09, 10, 11, 12, 13, 14, 15, 16, 17, 20]
     )
 );
+
+/// Solves a puzzle like [`solve_puzzle`], with parameters that change what the puzzle asks.
+///
+/// Panics if the puzzle takes none of the given parameters.
+pub fn solve_puzzle_with_params<I: Iterator<Item = String>>(
+    year: usize,
+    day: usize,
+    puzzle_number: usize,
+    input_lines: I,
+    params: &params::Parameters,
+) -> String {
+    match (year, day, puzzle_number) {
+        (2020, 1, 1) => year2020::day01::solve_puzzle1_with(input_lines, params),
+        (2020, 1, 2) => year2020::day01::solve_puzzle2_with(input_lines, params),
+        (2020, 3, 1) => year2020::day03::solve_puzzle1_with(input_lines, params),
+        (2020, 3, 2) => year2020::day03::solve_puzzle2_with(input_lines, params),
+        (2020, 9, 1) => year2020::day09::solve_puzzle1_with(input_lines, params),
+        (2020, 9, 2) => year2020::day09::solve_puzzle2_with(input_lines, params),
+        (2021, 1, 1) => year2021::day01::solve_puzzle1_with(input_lines, params),
+        (2021, 1, 2) => year2021::day01::solve_puzzle2_with(input_lines, params),
+        _ => {
+            params.expect_only(&[]);
+            solve_puzzle(year, day, puzzle_number, input_lines)
+        }
+    }
+}
+
+/// Renders the final state of a puzzle as an image, or returns `None` if the puzzle has no
+/// graphical representation.
+pub fn render_puzzle<I: Iterator<Item = String>>(
+    year: usize,
+    day: usize,
+    puzzle_number: usize,
+    input_lines: I,
+) -> Option<render::Raster> {
+    match (year, day, puzzle_number) {
+        (2020, 11, 1) => Some(year2020::day11::render_puzzle1(input_lines)),
+        (2020, 11, 2) => Some(year2020::day11::render_puzzle2(input_lines)),
+        (2021, 9, 1) => Some(year2021::day09::render_puzzle1(input_lines)),
+        (2021, 9, 2) => Some(year2021::day09::render_puzzle2(input_lines)),
+        (2021, 13, 1) => Some(year2021::day13::render_puzzle1(input_lines)),
+        (2021, 13, 2) => Some(year2021::day13::render_puzzle2(input_lines)),
+        (2021, 20, 1) => Some(year2021::day20::render_puzzle1(input_lines)),
+        (2021, 20, 2) => Some(year2021::day20::render_puzzle2(input_lines)),
+        _ => None,
+    }
+}
+
+/// Returns the frames of an animation of the simulation of a day, or `None` if the day has no
+/// step-by-step simulation to animate.
+pub fn animation_frames<'a, I: Iterator<Item = String> + 'a>(
+    year: usize,
+    day: usize,
+    input_lines: I,
+) -> Option<Box<dyn Iterator<Item = render::Raster> + 'a>> {
+    match (year, day) {
+        (2020, 11) => Some(Box::new(year2020::day11::animation_frames(input_lines))),
+        (2021, 11) => Some(Box::new(year2021::day11::animation_frames(input_lines))),
+        (2021, 17) => Some(Box::new(year2021::day17::animation_frames(input_lines))),
+        (2021, 20) => Some(Box::new(year2021::day20::animation_frames(input_lines))),
+        _ => None,
+    }
+}
+
+/// Explains what the puzzles of a day make of the input in the given format, or returns `None` if
+/// the day has no report in that format.
+///
+/// Panics if the report takes none of the given parameters.
+pub fn puzzle_report<I: Iterator<Item = String>>(
+    year: usize,
+    day: usize,
+    input_lines: I,
+    format: report::ReportFormat,
+    params: &params::Parameters,
+) -> Option<String> {
+    match (year, day) {
+        (2020, 3) => year2020::day03::slopes_report(input_lines, format, params),
+        (2020, 4) => {
+            params.expect_only(&[]);
+            year2020::day04::validation_report(input_lines, format)
+        }
+        (2020, 7) => {
+            params.expect_only(&[]);
+            Some(year2020::day07::rules_report(input_lines, format))
+        }
+        _ => None,
+    }
+}
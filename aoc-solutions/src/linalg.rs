@@ -0,0 +1,289 @@
+//! Square matrices over unsigned integers, for fast-forwarding linear recurrences.
+
+use std::fmt::Debug;
+use std::ops::{Index, IndexMut};
+
+/// An unsigned integer type that can be used as the entries of a [`SquareMatrix`].
+pub trait Element: Copy + Eq + Debug {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    /// Computes `self * other % modulus` without overflowing, given `self, other < modulus`.
+    fn mul_mod(self, other: Self, modulus: Self) -> Self;
+    /// Computes `(self + other) % modulus` without overflowing, given `self, other < modulus`.
+    fn add_mod(self, other: Self, modulus: Self) -> Self;
+    fn rem(self, modulus: Self) -> Self;
+}
+
+impl Element for u64 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u64::checked_add(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        u64::checked_mul(self, other)
+    }
+
+    fn mul_mod(self, other: Self, modulus: Self) -> Self {
+        (self as u128 * other as u128 % modulus as u128) as u64
+    }
+
+    fn add_mod(self, other: Self, modulus: Self) -> Self {
+        ((self as u128 + other as u128) % modulus as u128) as u64
+    }
+
+    fn rem(self, modulus: Self) -> Self {
+        self % modulus
+    }
+}
+
+impl Element for u128 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        u128::checked_add(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        u128::checked_mul(self, other)
+    }
+
+    fn mul_mod(self, other: Self, modulus: Self) -> Self {
+        if let Some(product) = self.checked_mul(other) {
+            return product % modulus;
+        }
+        // Double-and-add, as the product does not fit into 128 bits.
+        let (mut result, mut base, mut factor) = (0, self, other);
+        while factor > 0 {
+            if factor & 1 == 1 {
+                result = result.add_mod(base, modulus);
+            }
+            base = base.add_mod(base, modulus);
+            factor >>= 1;
+        }
+        result
+    }
+
+    fn add_mod(self, other: Self, modulus: Self) -> Self {
+        match self.checked_add(other) {
+            Some(sum) => sum % modulus,
+            // self + other < 2 * modulus, so a single subtraction suffices.
+            None => self.wrapping_add(other).wrapping_sub(modulus),
+        }
+    }
+
+    fn rem(self, modulus: Self) -> Self {
+        self % modulus
+    }
+}
+
+/// How the entries of matrix products are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arithmetic<T> {
+    /// Exact results, failing if any entry overflows.
+    Checked,
+    /// Results modulo the given (non-zero) modulus.
+    Modular(T),
+}
+
+impl<T: Element> Arithmetic<T> {
+    pub fn add(self, a: T, b: T) -> Option<T> {
+        match self {
+            Arithmetic::Checked => a.checked_add(b),
+            Arithmetic::Modular(modulus) => Some(a.add_mod(b, modulus)),
+        }
+    }
+
+    pub fn mul(self, a: T, b: T) -> Option<T> {
+        match self {
+            Arithmetic::Checked => a.checked_mul(b),
+            Arithmetic::Modular(modulus) => Some(a.mul_mod(b, modulus)),
+        }
+    }
+
+    pub fn reduce(self, a: T) -> T {
+        match self {
+            Arithmetic::Checked => a,
+            Arithmetic::Modular(modulus) => a.rem(modulus),
+        }
+    }
+}
+
+/// A square matrix with entries stored in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquareMatrix<T> {
+    size: usize,
+    entries: Vec<T>,
+}
+
+impl<T: Element> SquareMatrix<T> {
+    pub fn zero(size: usize) -> Self {
+        Self {
+            size,
+            entries: vec![T::ZERO; size * size],
+        }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut identity = Self::zero(size);
+        for i in 0..size {
+            identity[(i, i)] = T::ONE;
+        }
+        identity
+    }
+
+    /// Creates a matrix with the entry at `(row, column)` given by `f(row, column)`.
+    pub fn from_fn<F: FnMut(usize, usize) -> T>(size: usize, mut f: F) -> Self {
+        Self {
+            size,
+            entries: (0..size * size).map(|i| f(i / size, i % size)).collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Computes the product `self * other`, or `None` if an entry overflows.
+    pub fn mul(&self, other: &Self, arithmetic: Arithmetic<T>) -> Option<Self> {
+        assert_eq!(self.size, other.size, "matrix sizes must match");
+
+        let mut product = Self::zero(self.size);
+        for row in 0..self.size {
+            for k in 0..self.size {
+                let a = self[(row, k)];
+                if a == T::ZERO {
+                    continue;
+                }
+                for column in 0..self.size {
+                    let b = other[(k, column)];
+                    product[(row, column)] =
+                        arithmetic.add(product[(row, column)], arithmetic.mul(a, b)?)?;
+                }
+            }
+        }
+        Some(product)
+    }
+
+    /// Computes `self` to the power of `exp` by repeated squaring, or `None` if an entry of an
+    /// intermediate result overflows.
+    pub fn pow(&self, mut exp: u64, arithmetic: Arithmetic<T>) -> Option<Self> {
+        let mut result = Self::identity(self.size);
+        result
+            .entries
+            .iter_mut()
+            .for_each(|e| *e = arithmetic.reduce(*e));
+        let mut base = self.clone();
+        base.entries
+            .iter_mut()
+            .for_each(|e| *e = arithmetic.reduce(*e));
+
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base, arithmetic)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.mul(&base, arithmetic)?;
+            }
+        }
+        Some(result)
+    }
+
+    /// Computes the product of `self` with the column vector `vector`, or `None` if an entry
+    /// overflows.
+    pub fn mul_vector(&self, vector: &[T], arithmetic: Arithmetic<T>) -> Option<Vec<T>> {
+        assert_eq!(
+            self.size,
+            vector.len(),
+            "vector length must match matrix size"
+        );
+
+        (0..self.size)
+            .map(|row| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .try_fold(T::ZERO, |sum, (column, &v)| {
+                        arithmetic.add(
+                            sum,
+                            arithmetic.mul(self[(row, column)], arithmetic.reduce(v))?,
+                        )
+                    })
+            })
+            .collect()
+    }
+}
+
+impl<T> Index<(usize, usize)> for SquareMatrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        &self.entries[row * self.size + column]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for SquareMatrix<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+        &mut self.entries[row * self.size + column]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci_matrix<T: Element>() -> SquareMatrix<T> {
+        SquareMatrix::from_fn(2, |row, column| {
+            if row == 1 && column == 1 {
+                T::ZERO
+            } else {
+                T::ONE
+            }
+        })
+    }
+
+    #[test]
+    fn test_pow_checked() {
+        let fib = fibonacci_matrix::<u64>();
+        assert_eq!(
+            fib.pow(90, Arithmetic::Checked).unwrap()[(0, 1)],
+            2880067194370816120
+        );
+        assert_eq!(fib.pow(100, Arithmetic::Checked), None);
+        assert_eq!(
+            fibonacci_matrix::<u128>()
+                .pow(100, Arithmetic::Checked)
+                .unwrap()[(0, 1)],
+            354224848179261915075
+        );
+    }
+
+    #[test]
+    fn test_pow_modular() {
+        let fib = fibonacci_matrix::<u64>();
+        assert_eq!(
+            fib.pow(100, Arithmetic::Modular(1_000_000_007)).unwrap()[(0, 1)],
+            (354224848179261915075u128 % 1_000_000_007) as u64
+        );
+        // The Pisano period for 10 is 60.
+        assert_eq!(
+            fib.pow(1_000_000_000_000_000_000, Arithmetic::Modular(10))
+                .unwrap(),
+            fib.pow(1_000_000_000_000_000_000 % 60, Arithmetic::Modular(10))
+                .unwrap()
+        );
+        assert_eq!(
+            fibonacci_matrix::<u128>()
+                .pow(100, Arithmetic::Modular(u128::MAX - 1))
+                .unwrap()[(0, 1)],
+            354224848179261915075
+        );
+    }
+}
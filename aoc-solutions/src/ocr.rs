@@ -0,0 +1,332 @@
+//! Recognition of the capital letters some puzzles draw as a grid of dots.
+
+use std::fmt::{self, Display};
+
+use ndarray::{s, Array2, ArrayView2};
+
+const SMALL_GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+const LARGE_GLYPHS: [(char, &str); 15] = [
+    (
+        'A',
+        "..##...#..#.#....##....##....########....##....##....##....#",
+    ),
+    (
+        'B',
+        "#####.#....##....##....######.#....##....##....##....######.",
+    ),
+    (
+        'C',
+        ".####.#....##.....#.....#.....#.....#.....#.....#....#.####.",
+    ),
+    (
+        'E',
+        "#######.....#.....#.....#####.#.....#.....#.....#.....######",
+    ),
+    (
+        'F',
+        "#######.....#.....#.....#####.#.....#.....#.....#.....#.....",
+    ),
+    (
+        'G',
+        ".####.#....##.....#.....#.....#..####....##....##...##.###.#",
+    ),
+    (
+        'H',
+        "#....##....##....##....########....##....##....##....##....#",
+    ),
+    (
+        'J',
+        "...###....#.....#.....#.....#.....#.....#.#...#.#...#..###..",
+    ),
+    (
+        'K',
+        "#....##...#.#..#..#.#...##....##....#.#...#..#..#...#.#....#",
+    ),
+    (
+        'L',
+        "#.....#.....#.....#.....#.....#.....#.....#.....#.....######",
+    ),
+    (
+        'N',
+        "#....###...###...##.#..##.#..##..#.##..#.##...###...###....#",
+    ),
+    (
+        'P',
+        "#####.#....##....##....######.#.....#.....#.....#.....#.....",
+    ),
+    (
+        'R',
+        "#####.#....##....##....######.#..#..#...#.#...#.#....##....#",
+    ),
+    (
+        'X',
+        "#....##....#.#..#..#..#...##....##...#..#..#..#.#....##....#",
+    ),
+    (
+        'Z',
+        "######.....#.....#....#....#....#....#....#.....#.....######",
+    ),
+];
+
+/// One of the fonts in which letters are drawn by the puzzles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    /// Letters of 4x6 dots, five columns apart.
+    Small,
+    /// Letters of 6x10 dots, eight columns apart.
+    Large,
+}
+
+impl Font {
+    /// The font with the given letter height, if any.
+    pub fn for_height(height: usize) -> Option<Self> {
+        match height {
+            6 => Some(Font::Small),
+            10 => Some(Font::Large),
+            _ => None,
+        }
+    }
+
+    pub const fn glyph_width(self) -> usize {
+        match self {
+            Font::Small => 4,
+            Font::Large => 6,
+        }
+    }
+
+    pub const fn glyph_height(self) -> usize {
+        match self {
+            Font::Small => 6,
+            Font::Large => 10,
+        }
+    }
+
+    /// The distance between the first columns of two adjacent letters.
+    pub const fn pitch(self) -> usize {
+        match self {
+            Font::Small => 5,
+            Font::Large => 8,
+        }
+    }
+
+    fn glyphs(self) -> &'static [(char, &'static str)] {
+        match self {
+            Font::Small => &SMALL_GLYPHS,
+            Font::Large => &LARGE_GLYPHS,
+        }
+    }
+
+    /// Looks up the letter drawn in `glyph`, which must be of the size of a glyph of this font.
+    pub fn recognize_glyph(self, glyph: ArrayView2<bool>) -> Option<char> {
+        self.glyphs()
+            .iter()
+            .find(|(_, pattern)| {
+                pattern
+                    .bytes()
+                    .zip(glyph.iter())
+                    .all(|(pattern_byte, &lit)| (pattern_byte == b'#') == lit)
+            })
+            .map(|&(letter, _)| letter)
+    }
+}
+
+/// A glyph that does not match any letter of the font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownGlyph {
+    /// The position of the glyph in the text, starting at 0.
+    pub index: usize,
+    /// The first column of the glyph in the picture.
+    pub column: usize,
+    /// The glyph drawn with `#` and `.`, one line per row.
+    pub picture: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    /// The picture is not as high as the letters of any known font.
+    UnsupportedHeight(usize),
+    /// Some of the glyphs could not be recognized.
+    UnknownGlyphs {
+        /// The recognized text, with `?` in place of unknown glyphs.
+        text: String,
+        unknown_glyphs: Vec<UnknownGlyph>,
+    },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::UnsupportedHeight(height) => {
+                write!(f, "No font with letters {height} dots high")
+            }
+            OcrError::UnknownGlyphs {
+                text,
+                unknown_glyphs,
+            } => {
+                write!(f, "Unknown glyphs in \"{text}\"")?;
+                for glyph in unknown_glyphs {
+                    write!(
+                        f,
+                        "\nletter {} at column {}:\n{}",
+                        glyph.index, glyph.column, glyph.picture
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Draws `pixels` with `#` for lit and `.` for unlit pixels, one line per row.
+pub fn render_picture(pixels: ArrayView2<bool>) -> String {
+    pixels
+        .rows()
+        .into_iter()
+        .flat_map(|row| {
+            row.into_iter()
+                .map(|&lit| if lit { '#' } else { '.' })
+                .chain(['\n'])
+        })
+        .collect()
+}
+
+/// Reads the letters drawn in `pixels` in the given font.
+///
+/// Letters are expected to start at the first column and be spaced evenly.
+pub fn recognize_text_with_font(pixels: &Array2<bool>, font: Font) -> Result<String, OcrError> {
+    let (height, width) = pixels.dim();
+    if height != font.glyph_height() {
+        return Err(OcrError::UnsupportedHeight(height));
+    }
+
+    // Pad the last letter to the full glyph width, in case its rightmost columns are empty.
+    let num_letters = (width + font.pitch() - 1) / font.pitch();
+    let mut padded = Array2::from_elem((height, num_letters * font.pitch()), false);
+    padded.slice_mut(s![.., ..width]).assign(pixels);
+
+    let mut text = String::with_capacity(num_letters);
+    let mut unknown_glyphs = Vec::new();
+    for index in 0..num_letters {
+        let column = index * font.pitch();
+        let glyph = padded.slice(s![.., column..column + font.glyph_width()]);
+        match font.recognize_glyph(glyph) {
+            Some(letter) => text.push(letter),
+            None => {
+                text.push('?');
+                unknown_glyphs.push(UnknownGlyph {
+                    index,
+                    column,
+                    picture: render_picture(glyph),
+                });
+            }
+        }
+    }
+
+    if unknown_glyphs.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::UnknownGlyphs {
+            text,
+            unknown_glyphs,
+        })
+    }
+}
+
+/// Reads the letters drawn in `pixels`, choosing the font by the height of the picture.
+pub fn recognize_text(pixels: &Array2<bool>) -> Result<String, OcrError> {
+    let height = pixels.nrows();
+    let font = Font::for_height(height).ok_or(OcrError::UnsupportedHeight(height))?;
+    recognize_text_with_font(pixels, font)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    fn parse_picture(picture: &str) -> Array2<bool> {
+        let rows: Vec<Vec<bool>> = picture
+            .lines()
+            .map(|line| line.bytes().map(|b| b == b'#').collect())
+            .collect();
+        Array2::from_shape_fn((rows.len(), rows[0].len()), |(row, column)| {
+            rows[row][column]
+        })
+    }
+
+    #[test]
+    fn test_recognize_small() {
+        let picture = parse_picture(indoc! {"
+            ####.###..#..#.####.#....###..###..###.
+            #....#..#.#..#.#....#....#..#.#..#.#..#
+            ###..#..#.#..#.###..#....#..#.###..#..#
+            #....###..#..#.#....#....###..#..#.###.
+            #....#....#..#.#....#....#....#..#.#.#.
+            ####.#.....##..####.####.#....###..#..#
+        "});
+        assert_eq!(recognize_text(&picture), Ok(String::from("EPUELPBR")));
+    }
+
+    #[test]
+    fn test_recognize_large() {
+        let picture = parse_picture(indoc! {"
+            #....#..######
+            #....#..#.....
+            .#..#...#.....
+            .#..#...#.....
+            ..##....#####.
+            ..##....#.....
+            .#..#...#.....
+            .#..#...#.....
+            #....#..#.....
+            #....#..######
+        "});
+        assert_eq!(recognize_text(&picture), Ok(String::from("XE")));
+    }
+
+    #[test]
+    fn test_unknown_glyph() {
+        let picture = parse_picture(indoc! {"
+            #..#.####
+            #..#.#...
+            ####.###.
+            #..#.#...
+            #..#.#...
+            #.##.####
+        "});
+        assert_eq!(
+            recognize_text(&picture),
+            Err(OcrError::UnknownGlyphs {
+                text: String::from("?E"),
+                unknown_glyphs: vec![UnknownGlyph {
+                    index: 0,
+                    column: 0,
+                    picture: String::from("#..#\n#..#\n####\n#..#\n#..#\n#.##\n"),
+                }],
+            })
+        );
+    }
+}
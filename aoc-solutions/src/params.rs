@@ -0,0 +1,114 @@
+//! Parameters given on the command line that change what a puzzle asks, e.g. the slopes that the
+//! toboggan takes down the map of 2020 day 3.
+
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+/// A single parameter in the form `name=value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for Parameter {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or("parameter must be given as name=value")?;
+        if name.is_empty() {
+            return Err("parameter name must not be empty");
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+/// The parameters of a puzzle by name, where later parameters override earlier ones of the same
+/// name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parameters(BTreeMap<String, String>);
+
+impl FromIterator<Parameter> for Parameters {
+    fn from_iter<I: IntoIterator<Item = Parameter>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|parameter| (parameter.name, parameter.value))
+                .collect(),
+        )
+    }
+}
+
+impl Parameters {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Panics if there is a parameter with none of the given names, so that misspelled
+    /// parameters are not silently ignored.
+    pub fn expect_only(&self, names: &[&str]) {
+        if let Some(name) = self.0.keys().find(|name| !names.contains(&name.as_str())) {
+            if names.is_empty() {
+                panic!("Unknown parameter {name}! This puzzle takes no parameters");
+            }
+            panic!(
+                "Unknown parameter {name}! Must be one of: {}",
+                names.join(", ")
+            );
+        }
+    }
+
+    /// Parses the value of the parameter, or returns `default` if it is not given.
+    ///
+    /// Panics if the value cannot be parsed.
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> T {
+        match self.0.get(name) {
+            Some(value) => value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value {value:?} for parameter {name}")),
+            None => default,
+        }
+    }
+
+    /// Parses the comma-separated values of the parameter, or returns `default` if it is not
+    /// given.
+    ///
+    /// Panics if a value cannot be parsed.
+    pub fn get_list_or<T: FromStr>(&self, name: &str, default: Vec<T>) -> Vec<T> {
+        match self.0.get(name) {
+            Some(values) => values
+                .split(',')
+                .map(|value| {
+                    value.trim().parse().unwrap_or_else(|_| {
+                        panic!("Invalid value {value:?} in the list of parameter {name}")
+                    })
+                })
+                .collect(),
+            None => default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameters() {
+        let params: Parameters = ["width=3", "slopes=1, 2,3", "width=5"]
+            .iter()
+            .map(|s| s.parse::<Parameter>().unwrap())
+            .collect();
+        params.expect_only(&["width", "slopes"]);
+        assert_eq!(params.get_or("width", 0), 5);
+        assert_eq!(params.get_or("height", 7), 7);
+        assert_eq!(params.get_list_or::<u32>("slopes", vec![]), [1, 2, 3]);
+        assert!("=3".parse::<Parameter>().is_err());
+        assert!("width".parse::<Parameter>().is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! Export of grid-shaped puzzle states as image files.
+//!
+//! All formats are encoded without external dependencies. PNG files are written with
+//! uncompressed deflate blocks, which is fine for the small grids of the puzzles.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ndarray::{Array2, ArrayView2, Axis};
+
+/// An RGB color with 8 bits per channel.
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// Distinct colors for labelling regions, e.g. with `PALETTE[label % PALETTE.len()]`.
+pub const PALETTE: [Rgb; 10] = [
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [127, 127, 127],
+    [188, 189, 34],
+    [23, 190, 207],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Binary portable bitmap, with dark pixels black and light pixels white.
+    Pbm,
+    /// Binary portable graymap.
+    Pgm,
+    /// Binary portable pixmap.
+    Ppm,
+    Png,
+    /// SVG with one rectangle per run of equally colored pixels in a row.
+    Svg,
+}
+
+impl ImageFormat {
+    /// Determines the format from the extension of `path`, ignoring case.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pbm" => Some(ImageFormat::Pbm),
+            "pgm" => Some(ImageFormat::Pgm),
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+fn luminance([r, g, b]: Rgb) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+}
+
+/// A rectangular image, with one pixel per cell of a puzzle's grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raster {
+    pixels: Array2<Rgb>,
+}
+
+impl Raster {
+    pub fn from_pixels(pixels: Array2<Rgb>) -> Self {
+        Self { pixels }
+    }
+
+    /// Colors each cell of `grid` with `color`.
+    pub fn from_grid<C, F: FnMut(&C) -> Rgb>(grid: ArrayView2<C>, color: F) -> Self {
+        Self {
+            pixels: grid.map(color),
+        }
+    }
+
+    /// Draws lit cells in black and unlit cells in white, like `#` and `.` on paper.
+    pub fn from_bool_grid(grid: ArrayView2<bool>) -> Self {
+        Self::from_grid(grid, |&lit| if lit { BLACK } else { WHITE })
+    }
+
+    pub fn width(&self) -> usize {
+        self.pixels.ncols()
+    }
+
+    pub fn height(&self) -> usize {
+        self.pixels.nrows()
+    }
+
+    pub fn pixels(&self) -> &Array2<Rgb> {
+        &self.pixels
+    }
+
+    /// Gives mutable access to the pixels, e.g. to highlight single cells.
+    pub fn pixels_mut(&mut self) -> &mut Array2<Rgb> {
+        &mut self.pixels
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Pbm => self.encode_pbm(),
+            ImageFormat::Pgm => self.encode_pgm(),
+            ImageFormat::Ppm => self.encode_ppm(),
+            ImageFormat::Png => self.encode_png(),
+            ImageFormat::Svg => self.encode_svg().into_bytes(),
+        }
+    }
+
+    /// Writes the image to `path`, in the format given by its extension.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let format = ImageFormat::from_path(&path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Unsupported image file extension, expected one of pbm, pgm, ppm, png or svg",
+            )
+        })?;
+        fs::write(path, self.encode(format))
+    }
+
+    fn encode_pbm(&self) -> Vec<u8> {
+        let mut data = format!("P4\n{} {}\n", self.width(), self.height()).into_bytes();
+        for row in self.pixels.rows() {
+            for byte_pixels in row.to_vec().chunks(8) {
+                data.push(
+                    byte_pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &pixel)| luminance(pixel) < 128)
+                        .fold(0, |byte, (i, _)| byte | 0x80 >> i),
+                );
+            }
+        }
+        data
+    }
+
+    fn encode_pgm(&self) -> Vec<u8> {
+        let mut data = format!("P5\n{} {}\n255\n", self.width(), self.height()).into_bytes();
+        data.extend(self.pixels.iter().map(|&pixel| luminance(pixel)));
+        data
+    }
+
+    fn encode_ppm(&self) -> Vec<u8> {
+        let mut data = format!("P6\n{} {}\n255\n", self.width(), self.height()).into_bytes();
+        data.extend(self.pixels.iter().flatten());
+        data
+    }
+
+    fn encode_png(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width() as u32).to_be_bytes());
+        header.extend((self.height() as u32).to_be_bytes());
+        // Bit depth 8, truecolor, deflate, no filter, no interlacing.
+        header.extend([8, 2, 0, 0, 0]);
+        push_png_chunk(&mut png, b"IHDR", &header);
+
+        // Each scanline starts with filter type 0 (none).
+        let scanlines: Vec<u8> = self
+            .pixels
+            .rows()
+            .into_iter()
+            .flat_map(|row| std::iter::once(0).chain(row.into_iter().flatten().copied()))
+            .collect();
+        push_png_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+
+        push_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    fn encode_svg(&self) -> String {
+        const CELL_SIZE: usize = 10;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" shape-rendering="crispEdges">"#,
+            self.width() * CELL_SIZE,
+            self.height() * CELL_SIZE
+        )
+        .unwrap();
+        for (y, row) in self.pixels.rows().into_iter().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let color = row[x];
+                let run = row.iter().skip(x).take_while(|&&c| c == color).count();
+                writeln!(
+                    svg,
+                    r##"<rect x="{}" y="{}" width="{}" height="{CELL_SIZE}" fill="#{:02x}{:02x}{:02x}"/>"##,
+                    x * CELL_SIZE,
+                    y * CELL_SIZE,
+                    run * CELL_SIZE,
+                    color[0],
+                    color[1],
+                    color[2]
+                )
+                .unwrap();
+                x += run;
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Draws the image for a terminal with ANSI true color escape codes, two rows of pixels per
+    /// line of text using upper half blocks.
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for row_pair in self.pixels.axis_chunks_iter(Axis(0), 2) {
+            for column in row_pair.columns() {
+                let [r, g, b] = column[0];
+                write!(ansi, "\x1b[38;2;{r};{g};{b}m").unwrap();
+                match column.get(1) {
+                    Some([r, g, b]) => write!(ansi, "\x1b[48;2;{r};{g};{b}m").unwrap(),
+                    None => ansi.push_str("\x1b[49m"),
+                }
+                ansi.push('▀');
+            }
+            ansi.push_str("\x1b[0m\n");
+        }
+        ansi
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+fn push_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let crc_start = png.len();
+    png.extend(chunk_type);
+    png.extend(data);
+    let crc = crc32(&png[crc_start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK_LEN: usize = u16::MAX as usize;
+
+    let mut zlib = vec![0x78, 0x01];
+    let num_blocks = ((data.len() + MAX_BLOCK_LEN - 1) / MAX_BLOCK_LEN).max(1);
+    for (i, block) in data
+        .chunks(MAX_BLOCK_LEN)
+        .chain(data.is_empty().then_some(&[][..]))
+        .enumerate()
+    {
+        zlib.push((i + 1 == num_blocks) as u8);
+        zlib.extend((block.len() as u16).to_le_bytes());
+        zlib.extend((!(block.len() as u16)).to_le_bytes());
+        zlib.extend(block);
+    }
+    zlib.extend(adler32(data).to_be_bytes());
+    zlib
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::array;
+
+    #[test]
+    fn test_encode_pbm() {
+        let raster = Raster::from_bool_grid(
+            array![
+                [true, false, false, false, false, false, false, false, true],
+                [false, true, false, false, false, false, false, false, false]
+            ]
+            .view(),
+        );
+        assert_eq!(
+            raster.encode(ImageFormat::Pbm),
+            b"P4\n9 2\n\x80\x80\x40\x00".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_encode_png() {
+        let raster = Raster::from_pixels(Array2::from_elem((1, 1), [1, 2, 3]));
+        let png = raster.encode(ImageFormat::Png);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x01\0\0\0\x01\x08\x02"));
+        // Well-known CRC of the empty IEND chunk.
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+}
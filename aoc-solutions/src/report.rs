@@ -0,0 +1,68 @@
+//! Reports that explain what a puzzle makes of its input, in more detail than the solution.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Human-readable lines of text.
+    Text,
+    /// A single JSON value.
+    Json,
+    /// A Graphviz graph, for days whose input describes one.
+    Dot,
+}
+
+impl FromStr for ReportFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            "dot" => Ok(ReportFormat::Dot),
+            _ => Err("unknown report format, must be one of text, json or dot"),
+        }
+    }
+}
+
+/// Quotes the string as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Joins already encoded JSON values into a JSON array.
+pub fn json_array<I: IntoIterator<Item = String>>(values: I) -> String {
+    format!("[{}]", values.into_iter().collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(
+            json_string("hgt:\"59\\cm\"\n\u{1}"),
+            r#""hgt:\"59\\cm\"\n\u0001""#
+        );
+        assert_eq!(
+            json_array(["a", "b"].iter().map(|s| json_string(s))),
+            r#"["a","b"]"#
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Finding numbers in a list that add up to a target.
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use crate::windows::MinMaxQueue;
+
+/// Finds `k` numbers at distinct indices that sum to `target` and returns their indices in
+/// increasing order.
+///
+/// All numbers are looked up in a single hash map from each number to its indices, so pairs are
+/// found in one pass over the numbers, and larger subsets by fixing their first number and
+/// looking for the rest among the following ones. This takes `O(n^(k - 1))` time for `k >= 2`,
+/// one factor of `n` less than trying all subsets.
+pub fn k_sum(numbers: &[i64], k: usize, target: i64) -> Option<Vec<usize>> {
+    let mut indices_by_number: HashMap<i64, Vec<usize>> = HashMap::with_capacity(numbers.len());
+    for (index, &number) in numbers.iter().enumerate() {
+        indices_by_number.entry(number).or_default().push(index);
+    }
+
+    k_sum_from(numbers, &indices_by_number, 0, k, target)
+}
+
+/// Like [`k_sum`], but only considers the numbers from index `start` on.
+fn k_sum_from(
+    numbers: &[i64],
+    indices_by_number: &HashMap<i64, Vec<usize>>,
+    start: usize,
+    k: usize,
+    target: i64,
+) -> Option<Vec<usize>> {
+    // The first index of the number that is not before `start`.
+    let first_index_of = |number: i64| {
+        let indices = indices_by_number.get(&number)?;
+        indices
+            .get(indices.partition_point(|&index| index < start))
+            .copied()
+    };
+
+    match k {
+        0 => (target == 0).then(Vec::new),
+        1 => first_index_of(target).map(|index| vec![index]),
+        2 => (start..numbers.len()).find_map(|index| {
+            let other_index = first_index_of(target.checked_sub(numbers[index])?)?;
+            (other_index < index).then(|| vec![other_index, index])
+        }),
+        _ => (start..numbers.len()).find_map(|first_index| {
+            let rest_target = target.checked_sub(numbers[first_index])?;
+            let mut indices = k_sum_from(
+                numbers,
+                indices_by_number,
+                first_index + 1,
+                k - 1,
+                rest_target,
+            )?;
+            indices.insert(0, first_index);
+            Some(indices)
+        }),
+    }
+}
+
+/// The last `width` numbers of a sequence, which can tell whether a number is the sum of two of
+/// them at different positions.
+///
+/// Pushing a number only updates the counts of the numbers entering and leaving the window, and
+/// checking a number takes a single pass over the window.
+#[derive(Debug, Clone)]
+pub struct PairSumWindow {
+    width: usize,
+    window: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
+}
+
+impl PairSumWindow {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            window: VecDeque::with_capacity(width + 1),
+            counts: HashMap::with_capacity(width),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.window.len() == self.width
+    }
+
+    /// Adds a number to the window, dropping the oldest one if the window is full.
+    pub fn push(&mut self, number: u64) {
+        self.window.push_back(number);
+        *self.counts.entry(number).or_insert(0) += 1;
+
+        if self.window.len() > self.width {
+            let oldest = self.window.pop_front().unwrap();
+            let count = self.counts.get_mut(&oldest).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn is_pair_sum(&self, number: u64) -> bool {
+        self.window
+            .iter()
+            .filter_map(|&a| Some((a, number.checked_sub(a)?)))
+            .any(|(a, b)| {
+                let required_count = if a == b { 2 } else { 1 };
+                self.counts.get(&b).copied().unwrap_or(0) >= required_count
+            })
+    }
+}
+
+/// The index of the first number after the first `width` that is not the sum of two of the
+/// `width` numbers before it.
+pub fn first_non_pair_sum(numbers: &[u64], width: usize) -> Option<usize> {
+    let mut window = PairSumWindow::new(width);
+    for (index, &number) in numbers.iter().enumerate() {
+        if window.is_full() && !window.is_pair_sum(number) {
+            return Some(index);
+        }
+        window.push(number);
+    }
+    None
+}
+
+/// Finds the first range of at least `min_len` consecutive numbers that sum to `target`.
+///
+/// As the numbers are non-negative, the sum of a range only grows when it is extended, so a range
+/// is moved along the numbers with two pointers in linear time: each number is added at the end
+/// once, and numbers are dropped from the start while the sum exceeds the target.
+pub fn contiguous_range_with_sum(
+    numbers: &[u64],
+    target: u64,
+    min_len: usize,
+) -> Option<Range<usize>> {
+    contiguous_range_with_sum_min_max(numbers, target, min_len).map(|(range, ..)| range)
+}
+
+/// Finds the first range like [`contiguous_range_with_sum`] together with its smallest and
+/// largest number, which are kept in a [`MinMaxQueue`] of the range as it moves.
+pub fn contiguous_range_with_sum_min_max(
+    numbers: &[u64],
+    target: u64,
+    min_len: usize,
+) -> Option<(Range<usize>, u64, u64)> {
+    let mut start = 0;
+    let mut sum = 0;
+    let mut range_numbers = MinMaxQueue::new();
+    for (end, &number) in numbers.iter().enumerate() {
+        sum += number;
+        range_numbers.push(number);
+        while sum > target {
+            sum -= range_numbers.pop().unwrap();
+            start += 1;
+        }
+        // The start is the earliest one with a sum of at most the target, so if any range ending
+        // here has the target sum and is long enough, this one does.
+        if sum == target && range_numbers.len() >= min_len.max(1) {
+            return Some((
+                start..end + 1,
+                range_numbers.min().unwrap(),
+                range_numbers.max().unwrap(),
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_k_sum() {
+        let expenses = [1721, 979, 366, 299, 675, 1456];
+        assert_eq!(k_sum(&expenses, 2, 2020), Some(vec![0, 3]));
+        assert_eq!(k_sum(&expenses, 3, 2020), Some(vec![1, 2, 4]));
+        assert_eq!(
+            k_sum(&expenses, 4, 1721 + 979 + 366 + 1456),
+            Some(vec![0, 1, 2, 5])
+        );
+        assert_eq!(k_sum(&[1010], 2, 2020), None);
+        assert_eq!(k_sum(&[1010, 1010], 2, 2020), Some(vec![0, 1]));
+        assert_eq!(k_sum(&[-5, i64::MIN, 3], 2, -2), Some(vec![0, 2]));
+        assert_eq!(k_sum(&expenses, 0, 0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_xmas_example() {
+        let numbers = [
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
+            576,
+        ];
+        assert_eq!(first_non_pair_sum(&numbers, 5), Some(14));
+        assert_eq!(contiguous_range_with_sum(&numbers, 127, 2), Some(2..6));
+        assert_eq!(contiguous_range_with_sum(&numbers, 65, 1), Some(8..9));
+        assert_eq!(
+            contiguous_range_with_sum_min_max(&numbers, 127, 2),
+            Some((2..6, 15, 47))
+        );
+
+        // The same number twice in the window counts as a pair, but a single one does not.
+        assert_eq!(first_non_pair_sum(&[1, 1, 5, 2], 3), None);
+        assert_eq!(first_non_pair_sum(&[1, 3, 5, 2], 3), Some(3));
+        assert_eq!(contiguous_range_with_sum(&[4, 0, 9, 0], 9, 2), Some(1..3));
+    }
+}
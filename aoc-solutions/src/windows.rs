@@ -0,0 +1,177 @@
+//! Statistics over sliding windows of consecutive values, each computed in a single pass.
+
+use std::collections::VecDeque;
+use std::iter::FromIterator;
+
+/// Counts how often the sum of a window of `width` values is larger than the sum of the window
+/// before it.
+///
+/// Consecutive windows share all values but the first of the earlier and the last of the later
+/// window, so only those two are compared instead of summing the windows.
+///
+/// Panics if `width` is 0, as empty windows have no sums to compare.
+pub fn count_sum_increases<T: PartialOrd>(values: &[T], width: usize) -> usize {
+    assert!(width > 0, "The window width must be at least 1");
+
+    values
+        .iter()
+        .zip(values.iter().skip(width))
+        .filter(|(leaving, entering)| entering > leaving)
+        .count()
+}
+
+/// The averages of all windows of `width` values, from the first window on.
+pub fn moving_averages(values: &[i64], width: usize) -> Vec<f64> {
+    if width == 0 || values.len() < width {
+        return Vec::new();
+    }
+
+    let mut sum: i64 = values[..width].iter().sum();
+    let mut averages = Vec::with_capacity(values.len() - width + 1);
+    averages.push(sum as f64 / width as f64);
+    for (leaving, entering) in values.iter().zip(&values[width..]) {
+        sum += entering - leaving;
+        averages.push(sum as f64 / width as f64);
+    }
+    averages
+}
+
+/// A first-in, first-out queue that knows its smallest and largest value.
+///
+/// Besides the values, the queue keeps monotonic deques of the candidates for the minimum and the
+/// maximum: the values that are not larger, or not smaller, than any value pushed after them.
+/// All operations take amortized constant time.
+#[derive(Debug, Clone)]
+pub struct MinMaxQueue<T> {
+    values: VecDeque<T>,
+    /// The candidates for the minimum in increasing order.
+    minima: VecDeque<T>,
+    /// The candidates for the maximum in decreasing order.
+    maxima: VecDeque<T>,
+}
+
+impl<T: Ord + Copy> MinMaxQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            values: VecDeque::new(),
+            minima: VecDeque::new(),
+            maxima: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.values.push_back(value);
+
+        // Equal values are kept, so that popping one of them leaves the others.
+        while matches!(self.minima.back(), Some(&minimum) if minimum > value) {
+            self.minima.pop_back();
+        }
+        self.minima.push_back(value);
+        while matches!(self.maxima.back(), Some(&maximum) if maximum < value) {
+            self.maxima.pop_back();
+        }
+        self.maxima.push_back(value);
+    }
+
+    /// Removes the value that was pushed first.
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.values.pop_front()?;
+        if self.minima.front() == Some(&value) {
+            self.minima.pop_front();
+        }
+        if self.maxima.front() == Some(&value) {
+            self.maxima.pop_front();
+        }
+        Some(value)
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.minima.front().copied()
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.maxima.front().copied()
+    }
+}
+
+impl<T: Ord + Copy> Default for MinMaxQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Copy> FromIterator<T> for MinMaxQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut queue = Self::new();
+        for value in iter {
+            queue.push(value);
+        }
+        queue
+    }
+}
+
+/// The smallest and largest value of all windows of `width` values, from the first window on.
+pub fn sliding_min_max<T: Ord + Copy>(values: &[T], width: usize) -> Vec<(T, T)> {
+    if width == 0 {
+        return Vec::new();
+    }
+
+    let mut queue = MinMaxQueue::new();
+    values
+        .iter()
+        .filter_map(|&value| {
+            queue.push(value);
+            if queue.len() > width {
+                queue.pop();
+            }
+            (queue.len() == width).then(|| (queue.min().unwrap(), queue.max().unwrap()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_statistics() {
+        let depths = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(count_sum_increases(&depths, 1), 7);
+        assert_eq!(count_sum_increases(&depths, 3), 5);
+        assert_eq!(count_sum_increases(&depths, 10), 0);
+
+        assert_eq!(
+            moving_averages(&depths, 5),
+            [203.4, 205.0, 213.0, 225.2, 235.2, 247.8]
+        );
+        assert!(moving_averages(&depths, 11).is_empty());
+
+        assert_eq!(
+            sliding_min_max(&[3, 1, 1, 4, 1, 5, 9, 2], 3),
+            [(1, 3), (1, 4), (1, 4), (1, 5), (1, 9), (2, 9)]
+        );
+
+        let mut queue: MinMaxQueue<i32> = [2, 7, 1, 8].into_iter().collect();
+        assert_eq!((queue.min(), queue.max()), (Some(1), Some(8)));
+        queue.pop();
+        queue.pop();
+        queue.pop();
+        assert_eq!((queue.min(), queue.max()), (Some(8), Some(8)));
+        queue.pop();
+        assert_eq!(queue.min(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "The window width must be at least 1")]
+    fn test_empty_windows() {
+        count_sum_increases(&[1, 2, 3], 0);
+    }
+}
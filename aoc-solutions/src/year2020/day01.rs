@@ -1,57 +1,59 @@
-use std::convert::identity;
+use crate::params::Parameters;
+use crate::sums;
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let expenses: Vec<i64> = input_lines
+/// The sum the entries in question add up to.
+const TARGET: i64 = 2020;
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<i64> {
+    input_lines
         .map(|l| {
             l.parse::<i64>()
                 .expect("Input was not solely lines of integers")
         })
-        .collect();
-
-    let solution = expenses
-        .iter()
-        .enumerate()
-        .map(|(i, e0)| {
-            let mut maybe_solution = None;
-            for e1 in expenses.iter().skip(i) {
-                if e0 + e1 == 2020 {
-                    maybe_solution = Some(e0 * e1);
-                    break;
-                }
-            }
-            maybe_solution
-        })
-        .find_map(identity)
-        .expect("No two expenses add up to 2020");
+        .collect()
+}
 
-    solution.to_string()
+/// The product of `k` expenses that add up to `target`, if there are any.
+fn product_of_expenses_summing_to(expenses: &[i64], k: usize, target: i64) -> Option<i64> {
+    sums::k_sum(expenses, k, target).map(|indices| indices.iter().map(|&i| expenses[i]).product())
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let expenses: Vec<i64> = input_lines
-        .map(|l| {
-            l.parse::<i64>()
-                .expect("Input was not solely lines of integers")
-        })
-        .collect();
-
-    let solution = expenses
-        .iter()
-        .enumerate()
-        .map(|(i, e0)| {
-            let mut maybe_solution = None;
-            'outer: for (j, e1) in expenses.iter().skip(i).enumerate() {
-                for e2 in expenses.iter().skip(j) {
-                    if e0 + e1 + e2 == 2020 {
-                        maybe_solution = Some(e0 * e1 * e2);
-                        break 'outer;
-                    }
-                }
-            }
-            maybe_solution
-        })
-        .find_map(identity)
-        .expect("No three expenses add up to 2020");
+/// Multiplies `k` expenses, by default 2, that add up to `target`, by default 2020.
+pub fn solve_puzzle1_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    solve_with(input_lines, params, 2)
+}
+
+/// Multiplies `k` expenses, by default 3, that add up to `target`, by default 2020.
+pub fn solve_puzzle2_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    solve_with(input_lines, params, 3)
+}
+
+fn solve_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+    default_k: usize,
+) -> String {
+    params.expect_only(&["k", "target"]);
+    let expenses = parse_input(input_lines);
+    let k = params.get_or("k", default_k);
+    let target = params.get_or("target", TARGET);
+
+    let solution = product_of_expenses_summing_to(&expenses, k, target)
+        .unwrap_or_else(|| panic!("No {k} expenses add up to {target}"));
 
     solution.to_string()
 }
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle1_with(input_lines, &Parameters::default())
+}
+
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle2_with(input_lines, &Parameters::default())
+}
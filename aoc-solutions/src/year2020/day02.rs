@@ -1,51 +1,331 @@
+use std::fmt::{self, Display};
 use std::lazy::SyncLazy;
+use std::str::FromStr;
 
 use regex::Regex;
 
 static PASSWORD_LIST_RE: SyncLazy<Regex> = SyncLazy::new(|| {
-    Regex::new(
-        r"^(?P<min_usages>[0-9]+)-(?P<max_usages>[0-9]+) (?P<required_letter>[a-zA-Z]): (?P<password>[a-zA-Z]+)$",
-    ).unwrap()
+    Regex::new(r"^(?P<low>[0-9]+)-(?P<high>[0-9]+) (?P<letter>[a-zA-Z]): (?P<password>\S+)$")
+        .unwrap()
 });
 
-fn parse_input<I: Iterator<Item = String>>(
-    input_lines: I,
-) -> impl Iterator<Item = (usize, usize, String, String)> {
-    input_lines.map(|line| {
+/// A line of the password database: a password and the corporate policy it was set under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordEntry {
+    /// The first number of the policy, whose meaning depends on the policy.
+    pub low: usize,
+    /// The second number of the policy.
+    pub high: usize,
+    pub letter: char,
+    pub password: String,
+}
+
+impl FromStr for PasswordEntry {
+    type Err = &'static str;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
         let caps = PASSWORD_LIST_RE
-            .captures(&line)
-            .expect("A line did not match the expected input format");
-        (
-            caps.name("min_usages")
+            .captures(line)
+            .ok_or("line does not match the format \"1-3 a: abcde\"")?;
+        let number = |name| {
+            caps.name(name)
                 .unwrap()
                 .as_str()
-                .parse::<usize>()
-                .unwrap(),
-            caps.name("max_usages")
-                .unwrap()
-                .as_str()
-                .parse::<usize>()
-                .unwrap(),
-            caps.name("required_letter").unwrap().as_str().to_owned(),
-            caps.name("password").unwrap().as_str().to_owned(),
+                .parse()
+                .map_err(|_| "policy number too large")
+        };
+
+        Ok(Self {
+            low: number("low")?,
+            high: number("high")?,
+            letter: caps["letter"].chars().next().unwrap(),
+            password: caps["password"].to_owned(),
+        })
+    }
+}
+
+impl Display for PasswordEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} {}: {}",
+            self.low, self.high, self.letter, self.password
+        )
+    }
+}
+
+/// A rule that the password of an entry must follow.
+///
+/// Policies can be combined with [`Policy::and`], [`Policy::or`] and [`Policy::not`].
+pub trait Policy {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool;
+
+    /// States the rule for the entry, such as "'a' must occur 1 to 3 times".
+    fn describe(&self, entry: &PasswordEntry) -> String;
+
+    /// Describes the rule that the entry breaks, `None` if it follows the policy.
+    fn violation(&self, entry: &PasswordEntry) -> Option<String> {
+        (!self.is_satisfied(entry)).then(|| self.describe(entry))
+    }
+
+    fn and<P: Policy>(self, other: P) -> And<Self, P>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    fn or<P: Policy>(self, other: P) -> Or<Self, P>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+/// The policy of the sled rental place: the letter must occur at least `low` and at most `high`
+/// times.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LetterCount;
+
+impl Policy for LetterCount {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool {
+        let count = entry.password.matches(entry.letter).count();
+        (entry.low..=entry.high).contains(&count)
+    }
+
+    fn describe(&self, entry: &PasswordEntry) -> String {
+        format!(
+            "'{}' must occur {} to {} times",
+            entry.letter, entry.low, entry.high
+        )
+    }
+}
+
+/// The policy of the Official Toboggan Corporate Authentication System: the letter must be at
+/// exactly one of the 1-based positions `low` and `high`.
+///
+/// Positions past the end of the password do not hold the letter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LetterPositions;
+
+impl Policy for LetterPositions {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool {
+        let has_letter_at = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|index| entry.password.chars().nth(index))
+                == Some(entry.letter)
+        };
+        has_letter_at(entry.low) ^ has_letter_at(entry.high)
+    }
+
+    fn describe(&self, entry: &PasswordEntry) -> String {
+        format!(
+            "'{}' must be at exactly one of positions {} and {}",
+            entry.letter, entry.low, entry.high
+        )
+    }
+}
+
+/// At least `min_count` characters of the password must belong to a class of characters, such as
+/// digits.
+#[derive(Debug, Clone, Copy)]
+pub struct CharacterClass {
+    /// The name of the class in plural, such as "digits".
+    name: &'static str,
+    contains: fn(char) -> bool,
+    min_count: usize,
+}
+
+impl CharacterClass {
+    pub fn new(name: &'static str, contains: fn(char) -> bool, min_count: usize) -> Self {
+        Self {
+            name,
+            contains,
+            min_count,
+        }
+    }
+
+    pub fn lowercase(min_count: usize) -> Self {
+        Self::new("lowercase letters", char::is_lowercase, min_count)
+    }
+
+    pub fn uppercase(min_count: usize) -> Self {
+        Self::new("uppercase letters", char::is_uppercase, min_count)
+    }
+
+    pub fn digits(min_count: usize) -> Self {
+        Self::new("digits", |c| c.is_ascii_digit(), min_count)
+    }
+}
+
+impl Policy for CharacterClass {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool {
+        entry
+            .password
+            .chars()
+            .filter(|&c| (self.contains)(c))
+            .count()
+            >= self.min_count
+    }
+
+    fn describe(&self, _entry: &PasswordEntry) -> String {
+        format!(
+            "the number of {} must be at least {}",
+            self.name, self.min_count
         )
+    }
+}
+
+/// Both policies must be followed.
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(A, B);
+
+impl<A: Policy, B: Policy> Policy for And<A, B> {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool {
+        self.0.is_satisfied(entry) && self.1.is_satisfied(entry)
+    }
+
+    fn describe(&self, entry: &PasswordEntry) -> String {
+        format!(
+            "({} and {})",
+            self.0.describe(entry),
+            self.1.describe(entry)
+        )
+    }
+
+    /// Only names the first of the policies that is broken.
+    fn violation(&self, entry: &PasswordEntry) -> Option<String> {
+        self.0.violation(entry).or_else(|| self.1.violation(entry))
+    }
+}
+
+/// At least one of the policies must be followed.
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(A, B);
+
+impl<A: Policy, B: Policy> Policy for Or<A, B> {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool {
+        self.0.is_satisfied(entry) || self.1.is_satisfied(entry)
+    }
+
+    fn describe(&self, entry: &PasswordEntry) -> String {
+        format!("({} or {})", self.0.describe(entry), self.1.describe(entry))
+    }
+}
+
+/// The policy must be broken.
+#[derive(Debug, Clone, Copy)]
+pub struct Not<A>(A);
+
+impl<A: Policy> Policy for Not<A> {
+    fn is_satisfied(&self, entry: &PasswordEntry) -> bool {
+        !self.0.is_satisfied(entry)
+    }
+
+    fn describe(&self, entry: &PasswordEntry) -> String {
+        format!("not ({})", self.0.describe(entry))
+    }
+}
+
+/// Lists each entry that breaks the policy by its 1-based line number, together with the rule it
+/// breaks.
+pub fn invalid_entries_report<P: Policy>(entries: &[PasswordEntry], policy: &P) -> String {
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            policy
+                .violation(entry)
+                .map(|violation| format!("line {}: \"{entry}\" breaks rule: {violation}\n", i + 1))
+        })
+        .collect()
+}
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> impl Iterator<Item = PasswordEntry> {
+    input_lines.map(|line| {
+        line.parse()
+            .expect("A line did not match the expected input format")
     })
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+fn count_valid<I: Iterator<Item = String>, P: Policy>(input_lines: I, policy: P) -> usize {
     parse_input(input_lines)
-        .filter(|(min_u, max_u, req_l, pw)| (*min_u..=*max_u).contains(&pw.matches(req_l).count()))
+        .filter(|entry| policy.is_satisfied(entry))
         .count()
-        .to_string()
+}
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    count_valid(input_lines, LetterCount).to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    parse_input(input_lines)
-        .filter(|(first_pos, second_pos, req_l, pw)| {
-            let req_l = req_l.as_bytes()[0];
-            let pw = pw.as_bytes();
-            (pw[first_pos - 1] == req_l) ^ (pw[second_pos - 1] == req_l)
-        })
-        .count()
-        .to_string()
+    count_valid(input_lines, LetterPositions).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_entries() -> Vec<PasswordEntry> {
+        ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_official_policies() {
+        let entries = example_entries();
+        assert_eq!(
+            invalid_entries_report(&entries, &LetterCount),
+            "line 2: \"1-3 b: cdefg\" breaks rule: 'b' must occur 1 to 3 times\n"
+        );
+        assert_eq!(
+            invalid_entries_report(&entries, &LetterPositions),
+            "line 2: \"1-3 b: cdefg\" breaks rule: 'b' must be at exactly one of positions 1 \
+             and 3\nline 3: \"2-9 c: ccccccccc\" breaks rule: 'c' must be at exactly one of \
+             positions 2 and 9\n"
+        );
+
+        let past_end: PasswordEntry = "2-10 a: ba".parse().unwrap();
+        assert!(LetterPositions.is_satisfied(&past_end));
+        assert!("1-3 a abcde".parse::<PasswordEntry>().is_err());
+    }
+
+    #[test]
+    fn test_combined_policies() {
+        let policy = LetterCount
+            .and(CharacterClass::uppercase(1).or(CharacterClass::digits(2)))
+            .and(LetterPositions.not());
+        let entries: Vec<PasswordEntry> = ["1-3 a: abcde", "1-3 a: xaBcd", "1-2 a: aBcde"]
+            .iter()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        assert_eq!(
+            invalid_entries_report(&entries, &policy),
+            "line 1: \"1-3 a: abcde\" breaks rule: (the number of uppercase letters must be \
+             at least 1 or the number of digits must be at least 2)\nline 3: \"1-2 a: aBcde\" breaks rule: not ('a' \
+             must be at exactly one of positions 1 and 2)\n"
+        );
+    }
+
+    #[test]
+    fn test_digits_and_symbols() {
+        let entry: PasswordEntry = "1-3 a: a1b2-c3".parse().unwrap();
+        assert_eq!(entry.password, "a1b2-c3");
+        assert!(CharacterClass::digits(3).is_satisfied(&entry));
+        assert_eq!(
+            CharacterClass::digits(4).violation(&entry),
+            Some("the number of digits must be at least 4".to_owned())
+        );
+    }
 }
@@ -1,39 +1,297 @@
-fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<Vec<bool>> {
-    input_lines
-        .map(|line| {
-            line.as_bytes()
-                .iter()
-                .map(|c| match c {
-                    b'#' => true,
-                    b'.' => false,
-                    _ => panic!("Encountered unexpected character in input"),
-                })
-                .collect()
+use std::fmt::{self, Display, Write};
+use std::str::FromStr;
+
+use ndarray::Array2;
+
+use crate::params::Parameters;
+use crate::report::{self, ReportFormat};
+
+/// How far the toboggan moves right and down in each step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slope {
+    pub right: usize,
+    pub down: usize,
+}
+
+impl Slope {
+    pub const fn new(right: usize, down: usize) -> Self {
+        Self { right, down }
+    }
+}
+
+impl FromStr for Slope {
+    type Err = &'static str;
+
+    /// Parses a slope given as `right/down`, e.g. `3/1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (right, down) = s
+            .split_once('/')
+            .ok_or("slope must be given as right/down")?;
+        let right = right.parse().map_err(|_| "invalid number of steps right")?;
+        match down.parse() {
+            Ok(0) => Err("slope must move down at least one step"),
+            Ok(down) => Ok(Self { right, down }),
+            Err(_) => Err("invalid number of steps down"),
+        }
+    }
+}
+
+impl Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "right {}, down {}", self.right, self.down)
+    }
+}
+
+const PUZZLE1_SLOPE: Slope = Slope::new(3, 1);
+const PUZZLE2_SLOPES: [Slope; 5] = [
+    Slope::new(1, 1),
+    Slope::new(3, 1),
+    Slope::new(5, 1),
+    Slope::new(7, 1),
+    Slope::new(1, 2),
+];
+/// The largest number of steps right or down of the slopes searched for the fewest trees.
+const DEFAULT_MAX_STEPS: usize = 10;
+
+/// The open squares and trees of the area, whose pattern repeats to the right indefinitely.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TobogganMap {
+    trees: Array2<bool>,
+}
+
+impl TobogganMap {
+    pub fn width(&self) -> usize {
+        self.trees.ncols()
+    }
+
+    pub fn height(&self) -> usize {
+        self.trees.nrows()
+    }
+
+    pub fn is_tree(&self, row: usize, column: usize) -> bool {
+        self.trees[(row, column % self.width())]
+    }
+
+    /// The squares that the toboggan stops at from the top left to the bottom of the map, as row
+    /// and column, where the column keeps growing beyond the width of the pattern.
+    pub fn path(&self, slope: Slope) -> impl Iterator<Item = (usize, usize)> {
+        (0..self.height())
+            .step_by(slope.down)
+            .enumerate()
+            .map(move |(step, row)| (row, step * slope.right))
+    }
+
+    pub fn count_trees(&self, slope: Slope) -> usize {
+        self.path(slope)
+            .filter(|&(row, column)| self.is_tree(row, column))
+            .count()
+    }
+
+    /// Finds the slope with the fewest trees among those with at most `max_steps` steps right and
+    /// down, considering only slopes in lowest terms, of which `0/1` is the only one going straight
+    /// down. Ties go to the slope that moves down least, then to the one that moves right least.
+    pub fn fewest_trees(&self, max_steps: usize) -> Option<(Slope, usize)> {
+        (1..=max_steps)
+            .flat_map(|down| (0..=max_steps).map(move |right| Slope::new(right, down)))
+            .filter(|slope| gcd(slope.right, slope.down) == 1)
+            .map(|slope| (slope, self.count_trees(slope)))
+            .min_by_key(|&(_, trees)| trees)
+    }
+
+    /// Draws the map like the puzzle text, repeated to the right as far as the path goes, with
+    /// the squares the toboggan stops at marked `O` if they are open and `X` if there is a tree.
+    pub fn render_path(&self, slope: Slope) -> String {
+        let path: Vec<(usize, usize)> = self.path(slope).collect();
+        let last_column = path.last().map_or(0, |&(_, column)| column);
+        let num_columns = (last_column / self.width() + 1) * self.width();
+
+        let mut text = String::with_capacity((num_columns + 1) * self.height());
+        let mut stops = path.iter().peekable();
+        for row in 0..self.height() {
+            let stop = stops.next_if(|&&(stop_row, _)| stop_row == row);
+            for column in 0..num_columns {
+                text.push(
+                    match (self.is_tree(row, column), stop == Some(&(row, column))) {
+                        (false, false) => '.',
+                        (true, false) => '#',
+                        (false, true) => 'O',
+                        (true, true) => 'X',
+                    },
+                );
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> TobogganMap {
+    let mut width = None;
+    let trees: Vec<bool> = input_lines
+        .flat_map(|line| {
+            match width {
+                None => width = Some(line.len()),
+                Some(width) if width != line.len() => {
+                    panic!("Not all lines are of the same length")
+                }
+                Some(_) => (),
+            }
+            line.into_bytes().into_iter().map(|c| match c {
+                b'#' => true,
+                b'.' => false,
+                _ => panic!("Encountered unexpected character in input"),
+            })
         })
-        .collect()
+        .collect();
+
+    let width = width.expect("The map is empty");
+    TobogganMap {
+        trees: Array2::from_shape_vec((trees.len() / width, width), trees).unwrap(),
+    }
 }
 
-fn count_trees_encountered(tree_map: &Vec<Vec<bool>>, slope: (usize, usize)) -> usize {
-    let (dx, dy) = slope;
-    tree_map
-        .iter()
-        .step_by(dy)
-        .zip((0..).step_by(dx))
-        .filter_map(|(row, x)| row[x % row.len()].then_some(()))
-        .count()
+/// Counts the trees on each of the `slopes`, by default those of the second puzzle, and finds the
+/// slope with the fewest trees with at most `max_steps` steps right and down, by default 10.
+///
+/// The text report also draws the path of each slope.
+pub fn slopes_report<I: Iterator<Item = String>>(
+    input_lines: I,
+    format: ReportFormat,
+    params: &Parameters,
+) -> Option<String> {
+    params.expect_only(&["slopes", "max_steps"]);
+    let slopes = params.get_list_or("slopes", PUZZLE2_SLOPES.to_vec());
+    let max_steps = params.get_or("max_steps", DEFAULT_MAX_STEPS);
+
+    let map = parse_input(input_lines);
+    let fewest_trees = map.fewest_trees(max_steps);
+
+    match format {
+        ReportFormat::Text => {
+            let mut text = String::new();
+            for &slope in &slopes {
+                writeln!(text, "{slope}: {} trees", map.count_trees(slope)).unwrap();
+                text += &map.render_path(slope);
+            }
+            if let Some((slope, trees)) = fewest_trees {
+                writeln!(
+                    text,
+                    "fewest trees with up to {max_steps} steps: {trees} with {slope}"
+                )
+                .unwrap();
+            }
+            Some(text)
+        }
+        ReportFormat::Json => {
+            let slope_json = |slope: Slope, trees| {
+                format!(
+                    r#"{{"right":{},"down":{},"trees":{trees}}}"#,
+                    slope.right, slope.down
+                )
+            };
+            let fewest_trees =
+                fewest_trees.map_or("null".to_owned(), |(slope, trees)| slope_json(slope, trees));
+            Some(format!(
+                r#"{{"slopes":{},"fewest_trees":{fewest_trees}}}"#,
+                report::json_array(
+                    slopes
+                        .iter()
+                        .map(|&slope| slope_json(slope, map.count_trees(slope)))
+                )
+            ))
+        }
+        ReportFormat::Dot => None,
+    }
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let tree_map = parse_input(input_lines);
-    count_trees_encountered(&tree_map, (3, 1)).to_string()
+/// Counts the trees on the slope given by `slope=3/1`.
+pub fn solve_puzzle1_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    params.expect_only(&["slope"]);
+    let slope = params.get_or("slope", PUZZLE1_SLOPE);
+    parse_input(input_lines).count_trees(slope).to_string()
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let tree_map = parse_input(input_lines);
-    let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+/// Multiplies the numbers of trees on the slopes given by `slopes=1/1,3/1,5/1,7/1,1/2`.
+pub fn solve_puzzle2_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    params.expect_only(&["slopes"]);
+    let slopes = params.get_list_or("slopes", PUZZLE2_SLOPES.to_vec());
+    let map = parse_input(input_lines);
     slopes
         .iter()
-        .map(|slope| count_trees_encountered(&tree_map, *slope))
+        .map(|&slope| map.count_trees(slope))
         .product::<usize>()
         .to_string()
 }
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle1_with(input_lines, &Parameters::default())
+}
+
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle2_with(input_lines, &Parameters::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        ..##.......
+        #...#...#..
+        .#....#..#.
+        ..#.#...#.#
+        .#...##..#.
+        ..#.##.....
+        .#.#.#....#
+        .#........#
+        #.##...#...
+        #...##....#
+        .#..#...#.#
+    "};
+
+    #[test]
+    fn test_slopes() {
+        let map = parse_input(INPUT.lines().map(String::from));
+        let trees: Vec<usize> = PUZZLE2_SLOPES
+            .iter()
+            .map(|&slope| map.count_trees(slope))
+            .collect();
+        assert_eq!(trees, [2, 7, 3, 4, 2]);
+        assert_eq!("7/1".parse(), Ok(Slope::new(7, 1)));
+        assert!("7/0".parse::<Slope>().is_err());
+
+        let rendered = map.render_path(PUZZLE1_SLOPE);
+        assert_eq!(
+            rendered.lines().take(4).collect::<Vec<_>>(),
+            [
+                "O.##.........##.........##.......",
+                "#..O#...#..#...#...#..#...#...#..",
+                ".#....X..#..#....#..#..#....#..#.",
+                "..#.#...#O#..#.#...#.#..#.#...#.#",
+            ]
+        );
+
+        assert_eq!(map.count_trees(Slope::new(0, 1)), 3);
+        assert_eq!(map.fewest_trees(3), Some((Slope::new(1, 3), 0)));
+        assert_eq!(map.fewest_trees(0), None);
+
+        let clear_below = parse_input([".#", ".#", ".#"].into_iter().map(String::from));
+        assert_eq!(clear_below.fewest_trees(1), Some((Slope::new(0, 1), 0)));
+    }
+}
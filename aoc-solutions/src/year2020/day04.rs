@@ -1,95 +1,376 @@
+use std::borrow::Cow;
+use std::fmt::Write;
 use std::lazy::SyncLazy;
+use std::ops::RangeInclusive;
 
-use envy;
-use itertools::Itertools;
 use regex::Regex;
 use serde::Deserialize;
 use validator::{Validate, ValidationError};
 
+use crate::report::{self, ReportFormat};
+
+static HEIGHT_RE: SyncLazy<Regex> =
+    SyncLazy::new(|| Regex::new(r"^(?P<value>[0-9]+)(?P<unit>cm|in)$").unwrap());
 static HAIR_COLOR_RE: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"^#[0-9a-f]{6}$").unwrap());
 static EYE_COLOR_RE: SyncLazy<Regex> =
     SyncLazy::new(|| Regex::new(r"^(amb|blu|brn|gry|grn|hzl|oth)$").unwrap());
 static PASSPORT_ID_RE: SyncLazy<Regex> = SyncLazy::new(|| Regex::new(r"^[0-9]{9}$").unwrap());
 
+/// The fields of a passport, each `None` if it is missing, so that all missing fields are
+/// reported by the validation instead of only the first one by the deserialization.
 #[derive(Debug, Deserialize, Validate)]
 struct Passport {
     #[serde(rename = "byr")]
-    #[validate(range(min = 1920, max = 2002))]
-    birth_year: u32,
+    #[validate(
+        required,
+        range(min = 1920, max = 2002, message = "must be a year from 1920 to 2002")
+    )]
+    birth_year: Option<u32>,
     #[serde(rename = "iyr")]
-    #[validate(range(min = 2010, max = 2020))]
-    issue_year: u32,
+    #[validate(
+        required,
+        range(min = 2010, max = 2020, message = "must be a year from 2010 to 2020")
+    )]
+    issue_year: Option<u32>,
     #[serde(rename = "eyr")]
-    #[validate(range(min = 2020, max = 2030))]
-    expiration_year: u32,
+    #[validate(
+        required,
+        range(min = 2020, max = 2030, message = "must be a year from 2020 to 2030")
+    )]
+    expiration_year: Option<u32>,
     #[serde(rename = "hgt")]
-    #[validate(custom = "validate_height")]
-    height: String,
+    #[validate(required, custom = "validate_height")]
+    height: Option<String>,
     #[serde(rename = "hcl")]
-    #[validate(regex = "HAIR_COLOR_RE")]
-    hair_color: String,
+    #[validate(
+        required,
+        regex(
+            path = "HAIR_COLOR_RE",
+            message = "must be a # followed by six digits 0-9 or a-f"
+        )
+    )]
+    hair_color: Option<String>,
     #[serde(rename = "ecl")]
-    #[validate(regex = "EYE_COLOR_RE")]
-    eye_color: String,
+    #[validate(
+        required,
+        regex(
+            path = "EYE_COLOR_RE",
+            message = "must be one of amb, blu, brn, gry, grn, hzl and oth"
+        )
+    )]
+    eye_color: Option<String>,
     #[serde(rename = "pid")]
-    #[validate(regex = "PASSPORT_ID_RE")]
-    passport_id: String,
-    #[serde(rename = "cid", default)]
+    #[validate(
+        required,
+        regex(path = "PASSPORT_ID_RE", message = "must be a nine-digit number")
+    )]
+    passport_id: Option<String>,
+    // The country ID is optional so that the North Pole Credentials of Santa pass as well.
+    #[serde(rename = "cid")]
     _country_id: Option<String>,
 }
 
+/// The keys of the passport fields, which is also how validation errors refer to the fields.
+const KEYS: [&str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+
 fn validate_height(height_str: &str) -> Result<(), ValidationError> {
-    let height: u32 = height_str[..height_str.len() - 2]
-        .parse()
-        .map_err(|_| ValidationError::new("invalid_height"))?;
-
-    if height_str.ends_with("cm") && height >= 150 && height <= 193 {
-        Ok(())
-    } else if height_str.ends_with("in") && height >= 59 && height <= 76 {
-        Ok(())
-    } else {
-        Err(ValidationError::new("invalid_height"))
+    let invalid_height = |message: String| ValidationError {
+        message: Some(Cow::from(message)),
+        ..ValidationError::new("invalid_height")
+    };
+
+    let caps = HEIGHT_RE.captures(height_str).ok_or_else(|| {
+        invalid_height("must be a number followed by the unit cm or in".to_owned())
+    })?;
+    let (unit, range) = match &caps["unit"] {
+        "cm" => ("cm", 150..=193),
+        _ => ("in", 59..=76),
+    };
+    match caps["value"].parse::<u32>() {
+        Ok(height) if range.contains(&height) => Ok(()),
+        _ => Err(invalid_height(format!(
+            "must be from {} to {} {unit}",
+            range.start(),
+            range.end()
+        ))),
     }
 }
 
-fn parse_input<I: Iterator<Item = String>>(
-    input_lines: I,
-) -> impl Iterator<Item = Result<Passport, String>> {
-    input_lines
-        .coalesce(|current, next| {
-            let c_blank = current.trim().is_empty();
-            let n_blank = next.trim().is_empty();
-            match (c_blank, n_blank) {
-                (false, false) => Ok(format!("{} {}", current.trim(), next.trim())),
-                (false, true) => Err((current, next)),
-                (true, false) => Ok(next.trim().to_owned()),
-                (true, true) => Ok("".to_string()),
+fn deserialize_passport(fields: &[(&str, &str)]) -> Result<Passport, envy::Error> {
+    envy::from_iter(
+        fields
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.to_owned())),
+    )
+}
+
+/// A field whose value breaks the rule of the field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidField {
+    pub key: String,
+    pub value: String,
+    pub rule: String,
+}
+
+/// Everything that is wrong with a passport record of the batch file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassportReport {
+    /// The 1-based numbers of the first and the last line of the record.
+    pub lines: RangeInclusive<usize>,
+    /// The keys of the required fields that are missing, in the order of the rules.
+    pub missing_fields: Vec<&'static str>,
+    /// The fields that break their rule, including repeated fields, in the order of the record.
+    pub invalid_fields: Vec<InvalidField>,
+    /// The keys of the fields that passports do not have, in the order of the record.
+    pub unknown_fields: Vec<String>,
+}
+
+impl PassportReport {
+    fn check(lines: RangeInclusive<usize>, fields: &[(&str, &str)]) -> Self {
+        let is_known = |key: &str| KEYS.contains(&key);
+        let is_repeated = |i: usize| fields[..i].iter().any(|&(key, _)| key == fields[i].0);
+        // A repeated field or a value of the wrong type, like a year that is not a number, makes
+        // the whole record fail to deserialize, so those fields are left out of the validation.
+        let is_parsable = |field| deserialize_passport(&[field]).is_ok();
+
+        let validated_fields: Vec<(&str, &str)> = (0..fields.len())
+            .filter(|&i| is_known(fields[i].0) && !is_repeated(i) && is_parsable(fields[i]))
+            .map(|i| fields[i])
+            .collect();
+        let validation_errors = deserialize_passport(&validated_fields)
+            .expect("Fields that deserialize on their own deserialize together")
+            .validate()
+            .err()
+            .unwrap_or_default();
+        let field_errors = validation_errors.field_errors();
+
+        // Fields that are left out are invalid rather than missing.
+        let missing_fields = KEYS
+            .into_iter()
+            .filter(|key| {
+                fields.iter().all(|(other_key, _)| other_key != key)
+                    && field_errors
+                        .get(key)
+                        .into_iter()
+                        .flat_map(|errors| errors.iter())
+                        .any(|error| error.code == "required")
+            })
+            .collect();
+
+        let mut invalid_fields = Vec::new();
+        let mut unknown_fields = Vec::new();
+        for (i, &(key, value)) in fields.iter().enumerate() {
+            let rule = if !is_known(key) {
+                unknown_fields.push(key.to_owned());
+                continue;
+            } else if is_repeated(i) {
+                "must only be given once".to_owned()
+            } else if !is_parsable((key, value)) {
+                "must be a number".to_owned()
+            } else {
+                match field_errors.get(key).and_then(|errors| errors.first()) {
+                    Some(error) => error.message.as_ref().unwrap_or(&error.code).to_string(),
+                    None => continue,
+                }
+            };
+            invalid_fields.push(InvalidField {
+                key: key.to_owned(),
+                value: value.to_owned(),
+                rule,
+            });
+        }
+
+        Self {
+            lines,
+            missing_fields,
+            invalid_fields,
+            unknown_fields,
+        }
+    }
+
+    /// Whether the passport has all required fields, the check of the first part of the puzzle.
+    pub fn has_required_fields(&self) -> bool {
+        self.missing_fields.is_empty()
+    }
+
+    /// Whether the passport has all required fields and all fields follow their rules.
+    ///
+    /// Unknown fields are ignored, like the optional country ID.
+    pub fn is_valid(&self) -> bool {
+        self.has_required_fields() && self.invalid_fields.is_empty()
+    }
+
+    fn to_text(&self, passport_number: usize) -> String {
+        let mut text = format!(
+            "passport {passport_number} (lines {}-{}): {}\n",
+            self.lines.start(),
+            self.lines.end(),
+            if self.is_valid() { "valid" } else { "invalid" }
+        );
+        if !self.missing_fields.is_empty() {
+            writeln!(text, "  missing fields: {}", self.missing_fields.join(", ")).unwrap();
+        }
+        for field in &self.invalid_fields {
+            writeln!(text, "  {}:{} {}", field.key, field.value, field.rule).unwrap();
+        }
+        if !self.unknown_fields.is_empty() {
+            writeln!(text, "  unknown fields: {}", self.unknown_fields.join(", ")).unwrap();
+        }
+        text
+    }
+
+    fn to_json(&self, passport_number: usize) -> String {
+        let strings =
+            |strings: &[&str]| report::json_array(strings.iter().map(|s| report::json_string(s)));
+        let invalid_fields = report::json_array(self.invalid_fields.iter().map(|field| {
+            format!(
+                r#"{{"key":{},"value":{},"rule":{}}}"#,
+                report::json_string(&field.key),
+                report::json_string(&field.value),
+                report::json_string(&field.rule)
+            )
+        }));
+        let unknown_fields: Vec<&str> = self.unknown_fields.iter().map(String::as_str).collect();
+
+        format!(
+            r#"{{"passport":{passport_number},"first_line":{},"last_line":{},"valid":{},"missing_fields":{},"invalid_fields":{invalid_fields},"unknown_fields":{}}}"#,
+            self.lines.start(),
+            self.lines.end(),
+            self.is_valid(),
+            strings(&self.missing_fields),
+            strings(&unknown_fields)
+        )
+    }
+}
+
+/// Checks the passport records of the batch file, which are separated by blank lines.
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<PassportReport> {
+    let mut reports = Vec::new();
+    let mut record: Vec<(usize, String)> = Vec::new();
+
+    for (line_number, line) in (1..).zip(input_lines.map(Some).chain([None])) {
+        match line.filter(|line| !line.trim().is_empty()) {
+            Some(line) => record.push((line_number, line)),
+            // A blank line or the end of the file finishes the current record, if any.
+            None => {
+                if let (Some((first_line, _)), Some((last_line, _))) =
+                    (record.first(), record.last())
+                {
+                    let fields: Vec<(&str, &str)> = record
+                        .iter()
+                        .flat_map(|(_, line)| line.split_whitespace())
+                        .map(|field_str| field_str.split_once(':').unwrap_or((field_str, "")))
+                        .collect();
+                    reports.push(PassportReport::check(*first_line..=*last_line, &fields));
+                }
+                record.clear();
             }
-        })
-        .filter(|s| !s.trim().is_empty())
-        .map(|s| {
-            envy::from_iter(s.split_whitespace().map(|field_str| {
-                let mut field_parts = field_str.split(':');
-                let field_name = field_parts.next().unwrap();
-                let field_value = field_parts.next().unwrap();
-
-                (field_name.to_owned(), field_value.to_owned())
-            }))
-            .map_err(|_| s)
-        })
+        }
+    }
+
+    reports
+}
+
+/// Lists every passport record with its line range and what is wrong with it, `None` for formats
+/// other than text and JSON.
+pub fn validation_report<I: Iterator<Item = String>>(
+    input_lines: I,
+    format: ReportFormat,
+) -> Option<String> {
+    let reports = parse_input(input_lines);
+    let numbered_reports = (1..).zip(&reports);
+
+    match format {
+        ReportFormat::Text => Some(
+            numbered_reports
+                .map(|(passport_number, report)| report.to_text(passport_number))
+                .collect(),
+        ),
+        ReportFormat::Json => Some(report::json_array(
+            numbered_reports.map(|(passport_number, report)| report.to_json(passport_number)),
+        )),
+        ReportFormat::Dot => None,
+    }
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let passports = parse_input(input_lines);
-    passports.filter(Result::is_ok).count().to_string()
+    let reports = parse_input(input_lines);
+    reports
+        .iter()
+        .filter(|report| report.has_required_fields())
+        .count()
+        .to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let passports = parse_input(input_lines);
-    passports
-        .filter(Result::is_ok)
-        .map(Result::unwrap)
-        .filter(|pp| pp.validate().is_ok())
+    let reports = parse_input(input_lines);
+    reports
+        .iter()
+        .filter(|report| report.is_valid())
         .count()
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+        hcl:#623a2f
+
+        eyr:1972 cid:100
+        hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+        foo:bar
+
+        iyr:2019
+        hcl:#602927 eyr:1967 hgt:59cm ecl:grn
+        hgt:170cm
+    "};
+
+    #[test]
+    fn test_validation_report() {
+        let lines = || INPUT.lines().map(String::from);
+        let reports = parse_input(lines());
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].is_valid());
+        assert_eq!(reports[1].lines, 4..=6);
+        assert!(reports[1].has_required_fields());
+        assert_eq!(reports[1].unknown_fields, vec!["foo"]);
+        assert_eq!(reports[2].missing_fields, vec!["byr", "pid"]);
+
+        // A year that is not a number does not keep the other fields from being validated.
+        let report = PassportReport::check(1..=1, &[("byr", "19x0"), ("hgt", "77in")]);
+        let rules: Vec<&str> = report
+            .invalid_fields
+            .iter()
+            .map(|field| field.rule.as_str())
+            .collect();
+        assert_eq!(rules, ["must be a number", "must be from 59 to 76 in"]);
+        assert_eq!(report.missing_fields, ["iyr", "eyr", "hcl", "ecl", "pid"]);
+
+        assert_eq!(
+            validation_report(lines(), ReportFormat::Text).unwrap(),
+            indoc! {"
+                passport 1 (lines 1-2): valid
+                passport 2 (lines 4-6): invalid
+                  eyr:1972 must be a year from 2020 to 2030
+                  hgt:170 must be a number followed by the unit cm or in
+                  pid:186cm must be a nine-digit number
+                  unknown fields: foo
+                passport 3 (lines 8-10): invalid
+                  missing fields: byr, pid
+                  eyr:1967 must be a year from 2020 to 2030
+                  hgt:59cm must be from 150 to 193 cm
+                  hgt:170cm must only be given once
+            "}
+        );
+        assert!(validation_report(lines(), ReportFormat::Json)
+            .unwrap()
+            .starts_with(
+                r#"[{"passport":1,"first_line":1,"last_line":2,"valid":true,"missing_fields":[],"#
+            ));
+    }
+}
@@ -1,117 +1,291 @@
-use itertools::Itertools;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
 
-#[derive(Debug, Copy, Clone)]
-enum RowPart {
-    Front,
-    Back,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Seat {
+    pub row: u32,
+    pub column: u32,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum ColPart {
-    Left,
-    Right,
+/// How boarding passes encode seats by binary space partitioning: each letter picks the lower or
+/// upper half of the remaining rows, then of the remaining columns.
+///
+/// This makes each half of the code a binary number with the letter for the upper half as 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeatCode {
+    row_bits: u32,
+    column_bits: u32,
+    /// The letters for the lower and the upper half of the rows.
+    row_letters: [char; 2],
+    /// The letters for the lower and the upper half of the columns.
+    column_letters: [char; 2],
 }
 
-fn parse_input<I: Iterator<Item = String>>(
-    input_lines: I,
-) -> impl Iterator<Item = ([RowPart; 7], [ColPart; 3])> {
-    input_lines.map(|line| {
-        let (row_part_str, col_part_str) = line.split_at(7);
-        let mut row_part = [RowPart::Front; 7];
-        for (i, c) in row_part_str.as_bytes().iter().enumerate() {
-            row_part[i] = match c {
-                b'F' => RowPart::Front,
-                b'B' => RowPart::Back,
-                _ => panic!("Invalid input format"),
-            }
+impl Default for SeatCode {
+    /// The code of the airline's boarding passes, such as `FBFBBFFRLR`.
+    fn default() -> Self {
+        Self::new(7, 3, ['F', 'B'], ['L', 'R'])
+    }
+}
+
+impl SeatCode {
+    pub fn new(
+        row_bits: u32,
+        column_bits: u32,
+        row_letters: [char; 2],
+        column_letters: [char; 2],
+    ) -> Self {
+        assert!(
+            row_bits <= u32::BITS && column_bits <= u32::BITS,
+            "at most 32 bits are supported for rows and columns"
+        );
+        Self {
+            row_bits,
+            column_bits,
+            row_letters,
+            column_letters,
         }
-        let mut col_part = [ColPart::Left; 3];
-        for (i, c) in col_part_str.as_bytes().iter().enumerate() {
-            col_part[i] = match c {
-                b'L' => ColPart::Left,
-                b'R' => ColPart::Right,
-                _ => panic!("Invalid input format"),
-            }
+    }
+
+    pub const fn num_rows(&self) -> u64 {
+        1 << self.row_bits
+    }
+
+    pub const fn num_columns(&self) -> u64 {
+        1 << self.column_bits
+    }
+
+    pub fn decode(&self, boarding_pass: &str) -> Result<Seat, &'static str> {
+        let mut letters = boarding_pass.chars();
+        let mut decode_number = |bits, [lower, upper]: [char; 2]| {
+            (0..bits).try_fold(0u32, |number, _| {
+                let bit = match letters.next() {
+                    Some(letter) if letter == lower => 0,
+                    Some(letter) if letter == upper => 1,
+                    Some(_) => return Err("unexpected letter in boarding pass"),
+                    None => return Err("boarding pass too short"),
+                };
+                Ok(number << 1 | bit)
+            })
+        };
+
+        let row = decode_number(self.row_bits, self.row_letters)?;
+        let column = decode_number(self.column_bits, self.column_letters)?;
+        if letters.next().is_some() {
+            return Err("boarding pass too long");
         }
-        (row_part, col_part)
-    })
+
+        Ok(Seat { row, column })
+    }
+
+    /// The boarding pass of the seat, `None` if the seat is not on the plane.
+    pub fn encode(&self, seat: Seat) -> Option<String> {
+        if u64::from(seat.row) >= self.num_rows() || u64::from(seat.column) >= self.num_columns() {
+            return None;
+        }
+
+        let encode_number = |number: u32, bits, [lower, upper]: [char; 2]| {
+            (0..bits)
+                .rev()
+                .map(move |bit| if number >> bit & 1 == 1 { upper } else { lower })
+        };
+        Some(
+            encode_number(seat.row, self.row_bits, self.row_letters)
+                .chain(encode_number(
+                    seat.column,
+                    self.column_bits,
+                    self.column_letters,
+                ))
+                .collect(),
+        )
+    }
+
+    pub const fn seat_id(&self, seat: Seat) -> u64 {
+        (seat.row as u64) << self.column_bits | seat.column as u64
+    }
+
+    /// The seat with the given ID, `None` if the seat is not on the plane.
+    pub fn seat_from_id(&self, seat_id: u64) -> Option<Seat> {
+        (seat_id >> self.column_bits < self.num_rows()).then(|| Seat {
+            row: (seat_id >> self.column_bits) as u32,
+            column: (seat_id & (self.num_columns() - 1)) as u32,
+        })
+    }
 }
 
-fn compute_seat_details(row_part: [RowPart; 7], col_part: [ColPart; 3]) -> (u8, u8, u32) {
-    let row_num = {
-        let mut row_range = 0..128u8;
-        let mut level = 128 / 2;
-        for rp in row_part.iter() {
-            match rp {
-                RowPart::Front => row_range.end -= level,
-                RowPart::Back => row_range.start += level,
-            }
-            level /= 2;
+/// The runs of consecutive free seat IDs between the lowest and the highest occupied seat ID.
+pub fn gaps(occupied_ids: &BTreeSet<u64>) -> Vec<RangeInclusive<u64>> {
+    occupied_ids
+        .iter()
+        .zip(occupied_ids.iter().skip(1))
+        .filter(|(&id, &next_id)| id + 1 < next_id)
+        .map(|(&id, &next_id)| id + 1..=next_id - 1)
+        .collect()
+}
+
+/// The seats of a plane as occupied (`#`), free between occupied seats (`.`), or free at the
+/// front or back, which are missing from the plane (blank).
+pub struct OccupancyMap<'a> {
+    seat_code: &'a SeatCode,
+    occupied_ids: &'a BTreeSet<u64>,
+}
+
+impl<'a> OccupancyMap<'a> {
+    pub fn new(seat_code: &'a SeatCode, occupied_ids: &'a BTreeSet<u64>) -> Self {
+        Self {
+            seat_code,
+            occupied_ids,
         }
-        row_range.start
-    };
-    let col_num = {
-        let mut col_range = 0..8u8;
-        let mut level = 8 / 2;
-        for cp in col_part.iter() {
-            match cp {
-                ColPart::Left => col_range.end -= level,
-                ColPart::Right => col_range.start += level,
-            }
-            level /= 2;
+    }
+}
+
+impl Display for OccupancyMap<'_> {
+    /// Prints one line for each row with any occupied seats or gaps, headed by its boarding pass
+    /// prefix.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (first_id, last_id) = match (
+            self.occupied_ids.iter().next(),
+            self.occupied_ids.iter().next_back(),
+        ) {
+            (Some(&first_id), Some(&last_id)) => (first_id, last_id),
+            _ => return Ok(()),
+        };
+        let num_columns = self.seat_code.num_columns();
+
+        for row in first_id / num_columns..=last_id / num_columns {
+            let row_code = self
+                .seat_code
+                .encode(Seat {
+                    row: row as u32,
+                    column: 0,
+                })
+                .unwrap()
+                .chars()
+                .take(self.seat_code.row_bits as usize)
+                .collect::<String>();
+            let seats: String = (row * num_columns..(row + 1) * num_columns)
+                .map(|id| {
+                    if self.occupied_ids.contains(&id) {
+                        '#'
+                    } else if (first_id..=last_id).contains(&id) {
+                        '.'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            writeln!(f, "{row_code} {}", seats.trim_end())?;
         }
-        col_range.start
-    };
-    (row_num, col_num, row_num as u32 * 8 + col_num as u32)
+
+        Ok(())
+    }
+}
+
+fn parse_input<I: Iterator<Item = String>>(
+    input_lines: I,
+    seat_code: SeatCode,
+) -> impl Iterator<Item = Seat> {
+    input_lines.map(move |line| seat_code.decode(&line).expect("Invalid input format"))
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let seats = parse_input(input_lines);
-    seats
-        .map(|(row_part, col_part)| compute_seat_details(row_part, col_part))
-        .map(|(_, _, seat_id)| seat_id)
+    let seat_code = SeatCode::default();
+    parse_input(input_lines, seat_code)
+        .map(|seat| seat_code.seat_id(seat))
         .max()
         .unwrap()
         .to_string()
 }
 
+/// Finds the only free seat between two occupied ones, or reports all gaps together with the
+/// occupancy map if there is no such single seat.
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let seats = parse_input(input_lines);
-    seats
-        .map(|(row_part, col_part)| compute_seat_details(row_part, col_part))
-        .filter(|(row, _, _)| *row > 0 || *row < 127)
-        .map(|(_, _, id)| id)
-        .sorted()
-        .collect::<Vec<u32>>()
-        .windows(2)
-        .filter_map(|window| match window {
-            &[current_id, next_id] if current_id + 1 < next_id => Some((current_id + 1)..next_id),
-            _ => None,
+    let seat_code = SeatCode::default();
+    let occupied_ids: BTreeSet<u64> = parse_input(input_lines, seat_code)
+        .map(|seat| seat_code.seat_id(seat))
+        .collect();
+
+    let gaps = gaps(&occupied_ids);
+    let single_seats: Vec<u64> = gaps
+        .iter()
+        .filter(|gap| gap.start() == gap.end())
+        .map(|gap| *gap.start())
+        .collect();
+    if let [seat_id] = single_seats[..] {
+        return seat_id.to_string();
+    }
+
+    let gap_descriptions: Vec<String> = gaps
+        .iter()
+        .map(|gap| {
+            let [first, last] = [gap.start(), gap.end()].map(|&id| {
+                seat_code
+                    .encode(seat_code.seat_from_id(id).unwrap())
+                    .unwrap()
+            });
+            if gap.start() == gap.end() {
+                format!("{} ({first})", gap.start())
+            } else {
+                format!("{}-{} ({first}-{last})", gap.start(), gap.end())
+            }
         })
-        .flatten()
-        .next()
-        .unwrap()
-        .to_string()
+        .collect();
+    format!(
+        "expected exactly one free seat between occupied seats, found {} gaps: {}\n{}",
+        gaps.len(),
+        gap_descriptions.join(", "),
+        OccupancyMap::new(&seat_code, &occupied_ids)
+    )
 }
 
-// pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-//     let seats = parse_input(input_lines);
-//     let mut last_id = 0;
-//     for (_, _, id) in seats
-//         .map(|(row_part, col_part)| compute_seat_details(row_part, col_part))
-//         .sorted_by_key(|(_, _, id)| *id)
-//     {
-//         if last_id + 1 < id {
-//             for missing_id in (last_id + 1)..id {
-//                 println!(
-//                     "row: {}, col: {}, id: {}",
-//                     missing_id / 8,
-//                     missing_id % 8,
-//                     missing_id
-//                 );
-//             }
-//         }
-//         last_id = id;
-//     }
-//     "one of the above".to_owned()
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_and_encode() {
+        let seat_code = SeatCode::default();
+        for (boarding_pass, row, column, seat_id) in [
+            ("FBFBBFFRLR", 44, 5, 357),
+            ("BFFFBBFRRR", 70, 7, 567),
+            ("FFFBBBFRRR", 14, 7, 119),
+            ("BBFFBBFRLL", 102, 4, 820),
+        ] {
+            let seat = Seat { row, column };
+            assert_eq!(seat_code.decode(boarding_pass), Ok(seat));
+            assert_eq!(seat_code.seat_id(seat), seat_id);
+            assert_eq!(seat_code.seat_from_id(seat_id), Some(seat));
+            assert_eq!(seat_code.encode(seat).as_deref(), Some(boarding_pass));
+        }
+        assert!(seat_code.decode("FBFBBFFRL").is_err());
+        assert!(seat_code.decode("FBFBBFFRLRR").is_err());
+        assert!(seat_code.decode("FBFBBFFRLX").is_err());
+        assert_eq!(seat_code.seat_from_id(1024), None);
+
+        let seat_code = SeatCode::new(2, 4, ['0', '1'], ['<', '>']);
+        let seat = Seat { row: 2, column: 9 };
+        assert_eq!(seat_code.encode(seat).as_deref(), Some("10><<>"));
+        assert_eq!(seat_code.decode("10><<>"), Ok(seat));
+        assert_eq!(seat_code.seat_id(seat), 41);
+        assert_eq!(seat_code.encode(Seat { row: 4, column: 0 }), None);
+    }
+
+    #[test]
+    fn test_gaps_and_occupancy_map() {
+        let seat_code = SeatCode::new(2, 2, ['F', 'B'], ['L', 'R']);
+        let occupied_ids = BTreeSet::from([1, 2, 4, 5, 8, 9, 10]);
+        assert_eq!(gaps(&occupied_ids), vec![3..=3, 6..=7]);
+        assert_eq!(
+            OccupancyMap::new(&seat_code, &occupied_ids).to_string(),
+            "FF  ##.\nFB ##..\nBF ###\n"
+        );
+
+        let seat_code = SeatCode::new(2, 2, ['ä', 'ö'], ['é', 'ü']);
+        assert_eq!(seat_code.decode("öäüé"), Ok(Seat { row: 2, column: 2 }));
+        assert_eq!(
+            OccupancyMap::new(&seat_code, &occupied_ids).to_string(),
+            "ää  ##.\näö ##..\nöä ###\n"
+        );
+    }
+}
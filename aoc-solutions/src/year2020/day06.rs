@@ -1,52 +1,100 @@
-use std::collections::HashSet;
-
 use itertools::Itertools;
 
-fn parse_input<I: Iterator<Item = String>>(
-    input_lines: I,
-) -> impl Iterator<Item = Vec<HashSet<char>>> {
-    input_lines.batching(|it| {
-        let group_customs_decls = it
-            .take_while(|line| !line.is_empty())
-            .map(|person_customs_decl| person_customs_decl.chars().collect())
-            .collect::<Vec<HashSet<char>>>();
+use crate::letters::{self, LetterSet};
 
-        if group_customs_decls.is_empty() {
-            None
-        } else {
-            Some(group_customs_decls)
-        }
-    })
+/// How many people of a group must have answered a question with yes for it to count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quorum {
+    AtLeast(usize),
+    Everyone,
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let groups_customs_decls = parse_input(input_lines);
-    groups_customs_decls
-        .map(|mut group_customs_decls| {
-            group_customs_decls
-                .iter_mut()
-                .reduce(|acc, person_customs_decl| {
-                    acc.extend(person_customs_decl.iter());
-                    acc
+/// The questions answered with yes by each person of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub customs_decls: Vec<LetterSet>,
+}
+
+impl Group {
+    /// The questions answered with yes by at least as many people as the quorum requires.
+    pub fn questions_answered(&self, quorum: Quorum) -> LetterSet {
+        let min_people = match quorum {
+            Quorum::AtLeast(k) => k,
+            Quorum::Everyone => self.customs_decls.len(),
+        };
+        letters::in_at_least(self.customs_decls.iter().copied(), min_people)
+    }
+}
+
+/// Sums the numbers of questions answered by each group with the quorum.
+pub fn count_questions_answered(groups: &[Group], quorum: Quorum) -> usize {
+    groups
+        .iter()
+        .map(|group| group.questions_answered(quorum).len())
+        .sum()
+}
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<Group> {
+    input_lines
+        .batching(|it| {
+            let customs_decls = it
+                .take_while(|line| !line.is_empty())
+                .map(|person_customs_decl| {
+                    person_customs_decl
+                        .parse()
+                        .expect("Questions must be the letters a to z")
                 })
-                .map_or(0, |group_customs_decl| group_customs_decl.len())
+                .collect::<Vec<LetterSet>>();
+
+            (!customs_decls.is_empty()).then_some(Group { customs_decls })
         })
-        .sum::<usize>()
-        .to_string()
+        .collect()
+}
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let groups = parse_input(input_lines);
+    count_questions_answered(&groups, Quorum::AtLeast(1)).to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let groups_customs_decls = parse_input(input_lines);
-    groups_customs_decls
-        .map(|mut group_customs_decls| {
-            group_customs_decls
-                .iter_mut()
-                .reduce(|acc, person_customs_decl| {
-                    acc.retain(|c| person_customs_decl.contains(c));
-                    acc
-                })
-                .map_or(0, |group_customs_decl| group_customs_decl.len())
-        })
-        .sum::<usize>()
-        .to_string()
+    let groups = parse_input(input_lines);
+    count_questions_answered(&groups, Quorum::Everyone).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        abc
+
+        a
+        b
+        c
+
+        ab
+        ac
+
+        a
+        a
+        a
+        a
+
+        b
+    "};
+
+    #[test]
+    fn test_quorums() {
+        let groups = parse_input(INPUT.lines().map(String::from));
+        assert_eq!(groups.len(), 5);
+        assert_eq!(count_questions_answered(&groups, Quorum::AtLeast(1)), 11);
+        assert_eq!(count_questions_answered(&groups, Quorum::Everyone), 6);
+        assert_eq!(count_questions_answered(&groups, Quorum::AtLeast(2)), 2);
+        assert_eq!(
+            groups[2].questions_answered(Quorum::AtLeast(2)).to_string(),
+            "a"
+        );
+    }
 }
@@ -1,114 +1,416 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display};
 use std::lazy::SyncLazy;
 
 use regex::Regex;
 
+use crate::report::{self, ReportFormat};
+
 static CONTAINER_BAG_RE: SyncLazy<Regex> =
     SyncLazy::new(|| Regex::new(r"^(?P<name>[a-z ]+) bags contain").unwrap());
 static CONTAINED_BAGS_RE: SyncLazy<Regex> =
     SyncLazy::new(|| Regex::new(r"(?P<count>[0-9]+) (?P<name>[a-z ]+) bags?(?:,|.)").unwrap());
 
-fn parse_input<I: Iterator<Item = String>>(
-    input_lines: I,
-) -> HashMap<String, Vec<(usize, String)>> {
-    let mut bags_contains_map = HashMap::new();
-
-    input_lines.for_each(|line| {
-        let container_name = CONTAINER_BAG_RE
-            .captures(&line)
-            .expect("Line does not match expected format")
-            .name("name")
-            .unwrap()
-            .as_str();
-
-        if !bags_contains_map.contains_key(container_name) {
-            bags_contains_map.insert(container_name.to_owned(), Vec::new());
+/// The index of a bag color among all colors of a set of rules.
+pub type BagId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BagRulesError {
+    /// The line does not start like "light red bags contain".
+    InvalidLine(String),
+    /// Following the rules, a bag would have to contain itself. The colors of the cycle, starting
+    /// and ending with the same one.
+    Cycle(Vec<String>),
+}
+
+impl Display for BagRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BagRulesError::InvalidLine(line) => write!(f, "Invalid bag rule \"{line}\""),
+            BagRulesError::Cycle(cycle) => write!(
+                f,
+                "A {} bag would have to contain itself: {}",
+                cycle[0],
+                cycle.join(" -> ")
+            ),
         }
+    }
+}
+
+impl std::error::Error for BagRulesError {}
 
-        CONTAINED_BAGS_RE.captures_iter(&line).for_each(|caps| {
-            let count: usize = caps.name("count").unwrap().as_str().parse().unwrap();
-            let bag_name = caps.name("name").unwrap().as_str();
+/// The rules which bags must contain which other bags, as a directed acyclic graph from containers
+/// to their contents.
+#[derive(Debug, Clone)]
+pub struct BagRules {
+    names: Vec<String>,
+    ids: HashMap<String, BagId>,
+    /// For each bag, how many bags of which colors it contains directly.
+    contents: Vec<Vec<(u64, BagId)>>,
+    /// All bags, each before all bags it contains.
+    topological_order: Vec<BagId>,
+    /// For each bag, the total number of bags inside it.
+    total_contents: Vec<u64>,
+    /// For each bag, the bags that eventually contain it, in topological order.
+    all_containers: Vec<Vec<BagId>>,
+    /// For each bag, the bags that it eventually contains, in topological order.
+    all_contents: Vec<Vec<BagId>>,
+}
 
-            if !bags_contains_map.contains_key(bag_name) {
-                bags_contains_map.insert(bag_name.to_owned(), Vec::new());
+impl BagRules {
+    /// Parses rules such as "light red bags contain 1 bright white bag, 2 muted yellow bags.".
+    ///
+    /// Fails if a line is not a rule or if the rules would require a bag to contain itself.
+    pub fn parse<I: Iterator<Item = String>>(input_lines: I) -> Result<Self, BagRulesError> {
+        let mut names = Vec::new();
+        let mut ids = HashMap::new();
+        let mut contents = Vec::new();
+        let mut intern = |name: &str| -> BagId {
+            *ids.entry(name.to_owned()).or_insert_with(|| {
+                names.push(name.to_owned());
+                contents.push(Vec::new());
+                names.len() - 1
+            })
+        };
+
+        let mut rules = Vec::new();
+        for line in input_lines {
+            let container = match CONTAINER_BAG_RE.captures(&line) {
+                Some(caps) => intern(&caps["name"]),
+                None => return Err(BagRulesError::InvalidLine(line)),
+            };
+            for caps in CONTAINED_BAGS_RE.captures_iter(&line) {
+                let count = caps["count"]
+                    .parse()
+                    .map_err(|_| BagRulesError::InvalidLine(line.clone()))?;
+                rules.push((container, count, intern(&caps["name"])));
             }
+        }
+        for (container, count, bag) in rules {
+            contents[container].push((count, bag));
+        }
 
-            bags_contains_map
-                .get_mut(container_name)
-                .unwrap()
-                .push((count, bag_name.to_owned()))
-        });
-    });
+        let mut containers = vec![Vec::new(); names.len()];
+        for (container, bag_contents) in contents.iter().enumerate() {
+            for &(_, bag) in bag_contents {
+                containers[bag].push(container);
+            }
+        }
 
-    bags_contains_map
-}
+        let topological_order = topological_order(&contents).map_err(|cycle| {
+            BagRulesError::Cycle(cycle.into_iter().map(|bag| names[bag].clone()).collect())
+        })?;
 
-fn can_contain_bag(
-    bags_contains_map: &HashMap<String, Vec<(usize, String)>>,
-    container_bag_name: &str,
-    bag_name: &str,
-) -> bool {
-    let contained_bags = bags_contains_map.get(container_bag_name).unwrap();
-    if contained_bags
-        .iter()
-        .any(|(_, contained_bag)| contained_bag == bag_name)
-    {
-        true
-    } else {
-        contained_bags
-            .iter()
-            .any(|(_, contained_bag)| can_contain_bag(bags_contains_map, contained_bag, bag_name))
+        let mut total_contents = vec![0; names.len()];
+        for &bag in topological_order.iter().rev() {
+            total_contents[bag] = contents[bag]
+                .iter()
+                .map(|&(count, contained)| count * (1 + total_contents[contained]))
+                .sum();
+        }
+
+        // Containers come before their contents in topological order, so the reachable sets of
+        // the neighbors are complete when they are merged in.
+        let all_containers = reachable_sets(
+            &topological_order,
+            topological_order.iter().copied(),
+            |bag| containers[bag].iter().copied(),
+        );
+        let all_contents = reachable_sets(
+            &topological_order,
+            topological_order.iter().rev().copied(),
+            |bag| contents[bag].iter().map(|&(_, contained)| contained),
+        );
+
+        Ok(Self {
+            names,
+            ids,
+            contents,
+            topological_order,
+            total_contents,
+            all_containers,
+            all_contents,
+        })
+    }
+
+    pub fn id(&self, name: &str) -> Option<BagId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, bag: BagId) -> &str {
+        &self.names[bag]
+    }
+
+    pub fn num_bags(&self) -> usize {
+        self.names.len()
+    }
+
+    /// How many bags of which colors the bag contains directly.
+    pub fn contents(&self, bag: BagId) -> &[(u64, BagId)] {
+        &self.contents[bag]
+    }
+
+    /// All bags, each before all bags it contains.
+    pub fn topological_order(&self) -> &[BagId] {
+        &self.topological_order
+    }
+
+    /// The total number of bags inside the bag, counting the bags inside those as well.
+    pub fn total_contents(&self, bag: BagId) -> u64 {
+        self.total_contents[bag]
+    }
+
+    /// All bags that eventually contain the bag, in topological order, as precomputed by `parse`.
+    pub fn all_containers(&self, bag: BagId) -> &[BagId] {
+        &self.all_containers[bag]
+    }
+
+    /// All bags that the bag eventually contains, in topological order, as precomputed by `parse`.
+    pub fn all_contents(&self, bag: BagId) -> &[BagId] {
+        &self.all_contents[bag]
+    }
+
+    /// Exports the rules as a Graphviz graph with an edge from each container to each of its
+    /// contents, labeled with the number of contained bags.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph bags {\n");
+        for &bag in &self.topological_order {
+            dot += &format!("    {};\n", dot_id(&self.names[bag]));
+        }
+        for &container in &self.topological_order {
+            for &(count, bag) in &self.contents[container] {
+                dot += &format!(
+                    "    {} -> {} [label={count}];\n",
+                    dot_id(&self.names[container]),
+                    dot_id(&self.names[bag])
+                );
+            }
+        }
+        dot += "}\n";
+        dot
     }
 }
 
-fn count_contained_bags(
-    bags_contains_map: &HashMap<String, Vec<(usize, String)>>,
-    container_bag_name: &str,
-) -> usize {
-    let mut computed_counts = HashMap::new();
-    count_contained_bags_helper(&mut computed_counts, bags_contains_map, container_bag_name)
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
-fn count_contained_bags_helper(
-    computed_counts: &mut HashMap<String, usize>,
-    bags_contains_map: &HashMap<String, Vec<(usize, String)>>,
-    container_bag_name: &str,
-) -> usize {
-    let contained_bags_count = bags_contains_map
-        .get(container_bag_name)
-        .unwrap()
+/// For each bag, the bags reachable from it through `neighbors`, in topological order.
+///
+/// Each bag must be visited after all of its neighbors, so that the bags reachable from it are
+/// its neighbors and the bags reachable from those.
+fn reachable_sets<V, F, N>(
+    topological_order: &[BagId],
+    visiting_order: V,
+    neighbors: F,
+) -> Vec<Vec<BagId>>
+where
+    V: Iterator<Item = BagId>,
+    F: Fn(BagId) -> N,
+    N: Iterator<Item = BagId>,
+{
+    let num_bags = topological_order.len();
+    let mut is_reachable = vec![Vec::new(); num_bags];
+    for bag in visiting_order {
+        let mut is_reachable_from_bag = vec![false; num_bags];
+        for neighbor in neighbors(bag) {
+            is_reachable_from_bag[neighbor] = true;
+            for (reachable, &reachable_from_neighbor) in is_reachable_from_bag
+                .iter_mut()
+                .zip(&is_reachable[neighbor])
+            {
+                *reachable |= reachable_from_neighbor;
+            }
+        }
+        is_reachable[bag] = is_reachable_from_bag;
+    }
+
+    is_reachable
         .iter()
-        .map(|(count, contained_bag)| {
-            count
-                + count
-                    * computed_counts
-                        .get(contained_bag)
-                        .map(|contained_count| *contained_count)
-                        .unwrap_or_else(|| {
-                            count_contained_bags_helper(
-                                computed_counts,
-                                bags_contains_map,
-                                contained_bag,
-                            )
-                        })
+        .map(|is_reachable_from_bag| {
+            topological_order
+                .iter()
+                .copied()
+                .filter(|&bag| is_reachable_from_bag[bag])
+                .collect()
         })
-        .sum();
-    computed_counts.insert(container_bag_name.to_owned(), contained_bags_count);
-    contained_bags_count
+        .collect()
+}
+
+/// Orders the nodes so that each comes before all of its successors with Kahn's algorithm, or
+/// returns a cycle if there is none.
+fn topological_order(successors: &[Vec<(u64, BagId)>]) -> Result<Vec<BagId>, Vec<BagId>> {
+    let mut num_predecessors = vec![0; successors.len()];
+    for &(_, successor) in successors.iter().flatten() {
+        num_predecessors[successor] += 1;
+    }
+
+    let mut ready: VecDeque<BagId> = (0..successors.len())
+        .filter(|&node| num_predecessors[node] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(successors.len());
+    while let Some(node) = ready.pop_front() {
+        order.push(node);
+        for &(_, successor) in &successors[node] {
+            num_predecessors[successor] -= 1;
+            if num_predecessors[successor] == 0 {
+                ready.push_back(successor);
+            }
+        }
+    }
+    if order.len() == successors.len() {
+        return Ok(order);
+    }
+
+    // Every node left over has a predecessor that is left over as well, so walking backwards
+    // from any of them eventually runs into a cycle.
+    let mut predecessor = vec![None; successors.len()];
+    for (node, node_successors) in successors.iter().enumerate() {
+        for &(_, successor) in node_successors {
+            if num_predecessors[node] > 0 {
+                predecessor[successor].get_or_insert(node);
+            }
+        }
+    }
+    let mut visited_at = vec![None; successors.len()];
+    let mut walk = Vec::new();
+    let mut node = (0..successors.len())
+        .find(|&node| num_predecessors[node] > 0)
+        .unwrap();
+    while visited_at[node].is_none() {
+        visited_at[node] = Some(walk.len());
+        walk.push(node);
+        node = predecessor[node].unwrap();
+    }
+    // The walk went backwards, so the cycle runs through it in reverse from where it closed.
+    let mut cycle = vec![node];
+    cycle.extend(walk[visited_at[node].unwrap() + 1..].iter().rev());
+    cycle.push(node);
+    Err(cycle)
+}
+
+/// Summarizes each bag in topological order: what it contains directly, how many bags it
+/// contains in total and how many colors of bags eventually contain it.
+pub fn rules_report<I: Iterator<Item = String>>(input_lines: I, format: ReportFormat) -> String {
+    let rules = BagRules::parse(input_lines).unwrap_or_else(|err| panic!("{err}"));
+
+    match format {
+        ReportFormat::Text => rules
+            .topological_order()
+            .iter()
+            .map(|&bag| {
+                let contents: Vec<String> = rules
+                    .contents(bag)
+                    .iter()
+                    .map(|&(count, contained)| format!("{count} {}", rules.name(contained)))
+                    .collect();
+                format!(
+                    "{}: contains {} bags ({}), is inside {} colors of bags\n",
+                    rules.name(bag),
+                    rules.total_contents(bag),
+                    if contents.is_empty() {
+                        String::from("no other bags")
+                    } else {
+                        contents.join(", ")
+                    },
+                    rules.all_containers(bag).len()
+                )
+            })
+            .collect(),
+        ReportFormat::Json => report::json_array(rules.topological_order().iter().map(|&bag| {
+            let contents =
+                report::json_array(rules.contents(bag).iter().map(|&(count, contained)| {
+                    format!(
+                        r#"{{"count":{count},"bag":{}}}"#,
+                        report::json_string(rules.name(contained))
+                    )
+                }));
+            format!(
+                r#"{{"bag":{},"contents":{contents},"total_contents":{},"containers":{}}}"#,
+                report::json_string(rules.name(bag)),
+                rules.total_contents(bag),
+                rules.all_containers(bag).len()
+            )
+        })),
+        ReportFormat::Dot => rules.to_dot(),
+    }
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let bags_contains_map = parse_input(input_lines);
-    bags_contains_map
-        .keys()
-        .filter(|container_bag_name| {
-            can_contain_bag(&bags_contains_map, container_bag_name, "shiny gold")
-        })
-        .count()
-        .to_string()
+    let rules = BagRules::parse(input_lines).unwrap_or_else(|err| panic!("{err}"));
+    let shiny_gold = rules
+        .id("shiny gold")
+        .expect("no rule mentions shiny gold bags");
+
+    rules.all_containers(shiny_gold).len().to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let bags_contains_map = parse_input(input_lines);
-    count_contained_bags(&bags_contains_map, "shiny gold").to_string()
+    let rules = BagRules::parse(input_lines).unwrap_or_else(|err| panic!("{err}"));
+    let shiny_gold = rules
+        .id("shiny gold")
+        .expect("no rule mentions shiny gold bags");
+
+    rules.total_contents(shiny_gold).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        light red bags contain 1 bright white bag, 2 muted yellow bags.
+        dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+        bright white bags contain 1 shiny gold bag.
+        muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+        shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+        dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+        vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+        faded blue bags contain no other bags.
+        dotted black bags contain no other bags.
+    "};
+
+    #[test]
+    fn test_bag_rules() {
+        let rules = BagRules::parse(INPUT.lines().map(String::from)).unwrap();
+        let names =
+            |bags: &[BagId]| -> Vec<&str> { bags.iter().map(|&bag| rules.name(bag)).collect() };
+        let shiny_gold = rules.id("shiny gold").unwrap();
+
+        assert_eq!(
+            names(rules.all_containers(shiny_gold)),
+            ["light red", "dark orange", "bright white", "muted yellow"]
+        );
+        assert_eq!(
+            names(rules.all_contents(shiny_gold)),
+            ["dark olive", "vibrant plum", "faded blue", "dotted black"]
+        );
+        assert_eq!(rules.total_contents(shiny_gold), 32);
+        assert_eq!(rules.total_contents(rules.id("dotted black").unwrap()), 0);
+
+        let dot = rules.to_dot();
+        assert!(dot.starts_with("digraph bags {\n    \"light red\";\n"));
+        assert!(dot.contains("    \"shiny gold\" -> \"vibrant plum\" [label=2];\n"));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let rules = BagRules::parse(
+            [
+                "light red bags contain 1 bright white bag.",
+                "bright white bags contain 2 muted yellow bags, 1 dark olive bag.",
+                "muted yellow bags contain 1 light red bag.",
+                "dark olive bags contain no other bags.",
+            ]
+            .into_iter()
+            .map(String::from),
+        );
+        assert_eq!(
+            rules.unwrap_err().to_string(),
+            "A light red bag would have to contain itself: light red -> bright white -> muted \
+             yellow -> light red"
+        );
+    }
 }
@@ -1,64 +1,57 @@
-use std::convert::TryInto;
+use crate::params::Parameters;
+use crate::sums;
 
-fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<usize> {
+/// The number of previous numbers each number must be a sum of two of.
+const PREAMBLE_LEN: usize = 25;
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<u64> {
     input_lines.map(|line| line.parse().unwrap()).collect()
 }
 
-fn find_first_invalid(xmas_numbers: &Vec<usize>) -> Option<(usize, usize)> {
-    xmas_numbers
-        .windows(26)
-        .filter(|window| window.len() == 26)
-        .enumerate()
-        .find_map(|(i, window)| {
-            let prev_25: [usize; 25] = window[..25].try_into().unwrap();
-            let current = window[25];
-
-            for (j, a) in prev_25[..prev_25.len() - 1].iter().enumerate() {
-                for b in prev_25[(j + 1)..].iter() {
-                    if a + b == current {
-                        return None;
-                    }
-                }
-            }
-            Some((i + 25, current))
-        })
+/// The first number that is not the sum of two of the `preamble_len` numbers before it.
+fn find_first_invalid(xmas_numbers: &[u64], preamble_len: usize) -> Option<u64> {
+    sums::first_non_pair_sum(xmas_numbers, preamble_len).map(|i| xmas_numbers[i])
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let xmas_numbers = parse_input(input_lines);
+/// The sum of the smallest and largest number of the first range of at least two consecutive
+/// numbers that add up to the first invalid number.
+fn find_encryption_weakness(xmas_numbers: &[u64], preamble_len: usize) -> Option<u64> {
+    let invalid_number = find_first_invalid(xmas_numbers, preamble_len)?;
+    let (_, min, max) = sums::contiguous_range_with_sum_min_max(xmas_numbers, invalid_number, 2)?;
 
-    let (_first_match_i, first_match) = find_first_invalid(&xmas_numbers).unwrap();
-
-    first_match.to_string()
+    Some(min + max)
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+/// Finds the first invalid number with a preamble of `preamble` numbers, by default 25.
+pub fn solve_puzzle1_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    params.expect_only(&["preamble"]);
     let xmas_numbers = parse_input(input_lines);
 
-    let (_first_match_i, first_match) = find_first_invalid(&xmas_numbers).unwrap();
-
-    let mut solution = None;
+    find_first_invalid(&xmas_numbers, params.get_or("preamble", PREAMBLE_LEN))
+        .unwrap()
+        .to_string()
+}
 
-    for (i, a) in xmas_numbers[..(xmas_numbers.len() - 1)].iter().enumerate() {
-        let mut subset_sum = *a;
-        let mut min = *a;
-        let mut max = *a;
+/// Finds the encryption weakness with a preamble of `preamble` numbers, by default 25.
+pub fn solve_puzzle2_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    params.expect_only(&["preamble"]);
+    let xmas_numbers = parse_input(input_lines);
 
-        for b in xmas_numbers[(i + 1)..].iter() {
-            subset_sum += b;
-            if *b < min {
-                min = *b;
-            }
-            if *b > max {
-                max = *b;
-            }
+    find_encryption_weakness(&xmas_numbers, params.get_or("preamble", PREAMBLE_LEN))
+        .unwrap()
+        .to_string()
+}
 
-            if subset_sum == first_match {
-                solution = Some(min + max);
-                break;
-            }
-        }
-    }
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle1_with(input_lines, &Parameters::default())
+}
 
-    solution.unwrap().to_string()
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle2_with(input_lines, &Parameters::default())
 }
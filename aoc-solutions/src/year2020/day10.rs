@@ -1,59 +1,263 @@
-fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<usize> {
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+/// The joltage differences between adapters that the puzzle allows.
+const ALLOWED_STEPS: [u64; 3] = [1, 2, 3];
+/// How much higher the device's built-in adapter is rated than the highest adapter.
+const DEVICE_OFFSET: u64 = 3;
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<u64> {
     input_lines.map(|line| line.parse().unwrap()).collect()
 }
 
-pub fn compute_joltage_deltas<'a>(
-    charger_ratings: &'a Vec<usize>,
-) -> impl Iterator<Item = usize> + 'a {
-    charger_ratings
-        .iter()
-        .zip(charger_ratings[1..].iter())
-        .map(|(a, b)| b - a)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    /// Chaining all adapters requires a step between the two joltages that is not allowed.
+    DisallowedStep { from: u64, to: u64 },
+    /// No arrangement gets past the highest reachable joltage to the next higher one.
+    Unreachable { highest_reachable: u64, next: u64 },
+    /// There are more arrangements than fit into a `u128`.
+    Overflow,
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let mut charger_ratings = parse_input(input_lines);
+impl Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::DisallowedStep { from, to } => write!(
+                f,
+                "The step of {} jolts from {from} to {to} jolts is not allowed",
+                to - from
+            ),
+            ChainError::Unreachable {
+                highest_reachable,
+                next,
+            } => write!(
+                f,
+                "Nothing above {highest_reachable} jolts can be reached, so the next adapter \
+                 rated {next} jolts and the device cannot be connected"
+            ),
+            ChainError::Overflow => write!(f, "Too many arrangements to count"),
+        }
+    }
+}
 
-    charger_ratings.push(0);
-    charger_ratings.push(charger_ratings.iter().max().unwrap() + 3);
+impl std::error::Error for ChainError {}
 
-    charger_ratings.sort();
+/// A bag of adapters between the charging outlet and the device, which can be chained whenever
+/// the joltage rises by one of the allowed steps from one to the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterChain {
+    /// The joltages of the outlet, the adapters in ascending order and the device.
+    joltages: Vec<u64>,
+    allowed_steps: Vec<u64>,
+}
 
-    let (num_ones, num_threes) = compute_joltage_deltas(&charger_ratings).fold(
-        (0, 0),
-        |(num_ones, num_threes), dj| match dj {
-            1 => (num_ones + 1, num_threes),
-            3 => (num_ones, num_threes + 1),
-            _ => (num_ones, num_threes),
-        },
-    );
+impl AdapterChain {
+    /// Sorts the adapters between the outlet rated 0 jolts and the device rated `device_offset`
+    /// jolts above the highest adapter.
+    pub fn new(adapters: &[u64], allowed_steps: &[u64], device_offset: u64) -> Self {
+        let mut joltages = Vec::with_capacity(adapters.len() + 2);
+        joltages.push(0);
+        joltages.extend(adapters);
+        joltages.sort_unstable();
+        joltages.push(joltages.last().unwrap() + device_offset);
 
-    (num_ones * num_threes).to_string()
-}
+        let mut allowed_steps = allowed_steps.to_vec();
+        allowed_steps.sort_unstable();
+        allowed_steps.dedup();
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let mut charger_ratings = parse_input(input_lines);
-
-    charger_ratings.push(0);
-    charger_ratings.push(charger_ratings.iter().max().unwrap() + 3);
-
-    charger_ratings.sort();
-
-    let mut num_cfgs = [1, 0, 0];
-    for dj in compute_joltage_deltas(&charger_ratings) {
-        match dj {
-            1 => {
-                num_cfgs = [
-                    num_cfgs[0] + num_cfgs[1] + num_cfgs[2],
-                    num_cfgs[0],
-                    num_cfgs[1],
-                ]
+        Self {
+            joltages,
+            allowed_steps,
+        }
+    }
+
+    /// The joltages of the outlet, the adapters in ascending order and the device.
+    pub fn joltages(&self) -> &[u64] {
+        &self.joltages
+    }
+
+    /// Counts the steps between the joltages when all adapters are chained.
+    pub fn differences(&self) -> Result<BTreeMap<u64, usize>, ChainError> {
+        let mut differences = BTreeMap::new();
+        for &[from, to] in self.joltages.array_windows() {
+            if self.allowed_steps.binary_search(&(to - from)).is_err() {
+                return Err(ChainError::DisallowedStep { from, to });
             }
-            2 => num_cfgs = [num_cfgs[0] + num_cfgs[1], 0, num_cfgs[0]],
-            3 => num_cfgs = [num_cfgs[0], 0, 0],
-            _ => unreachable!("Unexpected joltage difference > 3 or < 1"),
+            *differences.entry(to - from).or_insert(0) += 1;
         }
+        Ok(differences)
+    }
+
+    /// The indices of the joltages that can directly follow the joltage at `index`.
+    fn successors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let max_step = self.allowed_steps.last().copied().unwrap_or(0);
+        let joltage = self.joltages[index];
+        (index + 1..self.joltages.len())
+            .take_while(move |&next| self.joltages[next] - joltage <= max_step)
+            .filter(move |&next| {
+                self.allowed_steps
+                    .binary_search(&(self.joltages[next] - joltage))
+                    .is_ok()
+            })
+    }
+
+    /// For each joltage, the number of arrangements from it to the device, computed from the
+    /// device backwards.
+    fn arrangements_to_device(&self) -> Result<Vec<u128>, ChainError> {
+        let mut counts = vec![0u128; self.joltages.len()];
+        *counts.last_mut().unwrap() = 1;
+        for index in (0..self.joltages.len() - 1).rev() {
+            counts[index] = self
+                .successors(index)
+                .try_fold(0u128, |count, next| count.checked_add(counts[next]))
+                .ok_or(ChainError::Overflow)?;
+        }
+
+        if counts[0] == 0 {
+            return Err(self.explain_unreachable());
+        }
+        Ok(counts)
+    }
+
+    /// Finds the highest joltage reachable from the outlet, which no allowed step leads past.
+    fn explain_unreachable(&self) -> ChainError {
+        let mut is_reachable = vec![false; self.joltages.len()];
+        is_reachable[0] = true;
+        for index in 0..self.joltages.len() {
+            if is_reachable[index] {
+                for next in self.successors(index).collect::<Vec<_>>() {
+                    is_reachable[next] = true;
+                }
+            }
+        }
+
+        let highest_reachable = is_reachable
+            .iter()
+            .rposition(|&reachable| reachable)
+            .unwrap();
+        ChainError::Unreachable {
+            highest_reachable: self.joltages[highest_reachable],
+            next: self.joltages[highest_reachable + 1],
+        }
+    }
+
+    /// Counts the distinct ways to connect the outlet to the device with some of the adapters.
+    pub fn count_arrangements(&self) -> Result<u128, ChainError> {
+        Ok(self.arrangements_to_device()?[0])
     }
 
-    num_cfgs.iter().sum::<usize>().to_string()
+    /// The arrangement with the given index among all arrangements in lexicographic order, as the
+    /// joltages from the outlet to the device, or `None` if there are not that many.
+    ///
+    /// Picking the index at random samples the arrangements uniformly.
+    pub fn nth_arrangement(&self, n: u128) -> Result<Option<Vec<u64>>, ChainError> {
+        let counts = self.arrangements_to_device()?;
+        Ok(self.unrank(&counts, n))
+    }
+
+    fn unrank(&self, counts: &[u128], mut n: u128) -> Option<Vec<u64>> {
+        if n >= counts[0] {
+            return None;
+        }
+
+        let mut index = 0;
+        let mut arrangement = vec![self.joltages[0]];
+        while index + 1 < self.joltages.len() {
+            // Skips the arrangements through the earlier successors.
+            for next in self.successors(index) {
+                if n < counts[next] {
+                    index = next;
+                    break;
+                }
+                n -= counts[next];
+            }
+            arrangement.push(self.joltages[index]);
+        }
+        Some(arrangement)
+    }
+
+    /// Lazily yields all arrangements in lexicographic order, as the joltages from the outlet to
+    /// the device.
+    pub fn arrangements(&self) -> Result<impl Iterator<Item = Vec<u64>> + '_, ChainError> {
+        let counts = self.arrangements_to_device()?;
+        Ok((0..counts[0]).map(move |n| self.unrank(&counts, n).unwrap()))
+    }
+}
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let adapters = parse_input(input_lines);
+    let chain = AdapterChain::new(&adapters, &ALLOWED_STEPS, DEVICE_OFFSET);
+
+    let differences = chain.differences().unwrap_or_else(|err| panic!("{err}"));
+    let count = |step| differences.get(&step).copied().unwrap_or(0);
+
+    (count(1) * count(3)).to_string()
+}
+
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let adapters = parse_input(input_lines);
+    let chain = AdapterChain::new(&adapters, &ALLOWED_STEPS, DEVICE_OFFSET);
+
+    chain
+        .count_arrangements()
+        .unwrap_or_else(|err| panic!("{err}"))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_ADAPTERS: [u64; 11] = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+
+    #[test]
+    fn test_example() {
+        let chain = AdapterChain::new(&EXAMPLE_ADAPTERS, &ALLOWED_STEPS, DEVICE_OFFSET);
+        assert_eq!(chain.differences(), Ok(BTreeMap::from([(1, 7), (3, 5)])));
+        assert_eq!(chain.count_arrangements(), Ok(8));
+
+        let arrangements: Vec<Vec<u64>> = chain.arrangements().unwrap().collect();
+        assert_eq!(arrangements.len(), 8);
+        assert_eq!(
+            arrangements[0],
+            [0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19, 22]
+        );
+        assert_eq!(arrangements[7], [0, 1, 4, 7, 10, 12, 15, 16, 19, 22]);
+        assert_eq!(chain.nth_arrangement(3), Ok(Some(arrangements[3].clone())));
+        assert_eq!(chain.nth_arrangement(8), Ok(None));
+    }
+
+    #[test]
+    fn test_other_steps_and_errors() {
+        // Only even steps leave out the odd adapters.
+        let chain = AdapterChain::new(&[1, 2, 3, 4, 6], &[2, 4], 2);
+        assert_eq!(
+            chain.differences(),
+            Err(ChainError::DisallowedStep { from: 0, to: 1 })
+        );
+        assert_eq!(
+            chain.arrangements().unwrap().collect::<Vec<_>>(),
+            [
+                vec![0, 2, 4, 6, 8],
+                vec![0, 2, 4, 8],
+                vec![0, 2, 6, 8],
+                vec![0, 4, 6, 8],
+                vec![0, 4, 8]
+            ]
+        );
+
+        let chain = AdapterChain::new(&[1, 2, 7, 8], &ALLOWED_STEPS, DEVICE_OFFSET);
+        assert_eq!(
+            chain.count_arrangements(),
+            Err(ChainError::Unreachable {
+                highest_reachable: 2,
+                next: 7
+            })
+        );
+
+        let adapters: Vec<u64> = (1..=200).collect();
+        let chain = AdapterChain::new(&adapters, &ALLOWED_STEPS, DEVICE_OFFSET);
+        assert_eq!(chain.count_arrangements(), Err(ChainError::Overflow));
+    }
 }
@@ -2,6 +2,9 @@ use std::fmt::{self, Display};
 
 use ndarray::Array2;
 
+use crate::automaton::{Automaton, Boundary, LineOfSight, Moore};
+use crate::render::{self, Raster, Rgb};
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 enum SeatState {
     Empty,
@@ -51,175 +54,109 @@ fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Array2<SeatState>
     .unwrap()
 }
 
-#[inline]
-fn checked_add(a: usize, b: isize) -> Option<usize> {
-    if b >= 0 {
-        a.checked_add(b as usize)
-    } else {
-        a.checked_sub((-b) as usize)
-    }
-}
+type SeatingRule = fn(SeatState, &[SeatState]) -> SeatState;
 
-const NEIGHBORHOOD: [(isize, isize); 8] = [
-    (-1, -1),
-    (0, -1),
-    (1, -1),
-    (-1, 0),
-    (1, 0),
-    (-1, 1),
-    (0, 1),
-    (1, 1),
-];
-
-struct StateUpdateIter<'a> {
-    previous_state: &'a Array2<SeatState>,
-    x: usize,
-    y: usize,
+fn count_occupied<'a, I: IntoIterator<Item = &'a SeatState>>(seat_states: I) -> usize {
+    seat_states
+        .into_iter()
+        .filter(|s| **s == SeatState::Occupied)
+        .count()
 }
 
-impl<'a> StateUpdateIter<'a> {
-    pub fn new(current_state: &'a Array2<SeatState>) -> Self {
-        Self {
-            previous_state: current_state,
-            x: 0,
-            y: 0,
-        }
+fn seating_rule(seat_state: SeatState, neighbors: &[SeatState]) -> SeatState {
+    match (seat_state, count_occupied(neighbors)) {
+        (SeatState::Empty, 0) => SeatState::Occupied,
+        (SeatState::Occupied, 4..=8) => SeatState::Empty,
+        (old_state, _) => old_state,
     }
 }
 
-impl<'a> Iterator for StateUpdateIter<'a> {
-    type Item = SeatState;
-
-    fn next(&mut self) -> std::option::Option<<Self as Iterator>::Item> {
-        if self.y < self.previous_state.nrows() && self.x < self.previous_state.ncols() {
-            let occupied_neighboring_seats = NEIGHBORHOOD
-                .iter()
-                .filter_map(|(dx, dy)| {
-                    self.previous_state
-                        .get((checked_add(self.y, *dy)?, checked_add(self.x, *dx)?))
-                })
-                .filter(|state| **state == SeatState::Occupied)
-                .count();
-
-            let old_state = self.previous_state[[self.y, self.x]];
-            let new_state = match (old_state, occupied_neighboring_seats) {
-                (SeatState::Empty, 0) => SeatState::Occupied,
-                (SeatState::Occupied, 4..=8) => SeatState::Empty,
-                (old_state, _) => old_state,
-            };
-
-            if self.x + 1 == self.previous_state.ncols() {
-                self.x = 0;
-                self.y += 1;
-            } else {
-                self.x += 1;
-            }
-
-            Some(new_state)
-        } else {
-            None
-        }
-    }
+fn seating_automaton(seat_states: Array2<SeatState>) -> Automaton<SeatState, Moore, SeatingRule> {
+    Automaton::new(
+        seat_states,
+        Moore::default(),
+        seating_rule,
+        Boundary::Fixed(SeatState::NotPresent),
+    )
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let mut seat_states = parse_input(input_lines);
+    let mut seating = seating_automaton(parse_input(input_lines));
 
-    loop {
-        let previous_states = seat_states.clone();
-        seat_states
-            .iter_mut()
-            .zip(StateUpdateIter::new(&previous_states))
-            .for_each(|(target, src)| *target = src);
+    seating.run_until_stable();
 
-        if seat_states == previous_states {
-            break;
-        }
+    count_occupied(seating.cells()).to_string()
+}
+
+fn tweaked_seating_rule(seat_state: SeatState, neighbors: &[SeatState]) -> SeatState {
+    match (seat_state, count_occupied(neighbors)) {
+        (SeatState::Empty, 0) => SeatState::Occupied,
+        (SeatState::Occupied, 5..=8) => SeatState::Empty,
+        (old_state, _) => old_state,
     }
+}
 
-    seat_states
-        .iter()
-        .filter(|s| **s == SeatState::Occupied)
-        .count()
-        .to_string()
+type TweakedSeatingAutomaton =
+    Automaton<SeatState, LineOfSight<fn(SeatState) -> bool>, SeatingRule>;
+
+fn tweaked_seating_automaton(seat_states: Array2<SeatState>) -> TweakedSeatingAutomaton {
+    Automaton::new(
+        seat_states,
+        LineOfSight::new(|seat_state| seat_state == SeatState::NotPresent),
+        tweaked_seating_rule,
+        Boundary::Fixed(SeatState::NotPresent),
+    )
 }
 
-struct TweakedStateUpdateIter<'a> {
-    previous_state: &'a Array2<SeatState>,
-    x: usize,
-    y: usize,
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let mut seating = tweaked_seating_automaton(parse_input(input_lines));
+
+    seating.run_until_stable();
+
+    count_occupied(seating.cells()).to_string()
 }
 
-impl<'a> TweakedStateUpdateIter<'a> {
-    pub fn new(current_state: &'a Array2<SeatState>) -> Self {
-        Self {
-            previous_state: current_state,
-            x: 0,
-            y: 0,
-        }
+fn seat_color(seat_state: &SeatState) -> Rgb {
+    match seat_state {
+        SeatState::Empty => [44, 160, 44],
+        SeatState::Occupied => [214, 39, 40],
+        SeatState::NotPresent => render::WHITE,
     }
 }
 
-impl<'a> Iterator for TweakedStateUpdateIter<'a> {
-    type Item = SeatState;
-
-    fn next(&mut self) -> std::option::Option<<Self as Iterator>::Item> {
-        if self.y < self.previous_state.nrows() && self.x < self.previous_state.ncols() {
-            let occupied_neighboring_seats = NEIGHBORHOOD
-                .iter()
-                .filter_map(|(dx, dy)| {
-                    itertools::iterate((*dx, *dy), |(dx2, dy2)| (*dx2 + dx, *dy2 + dy))
-                        .map(|(dx, dy)| {
-                            self.previous_state
-                                .get((checked_add(self.y, dy)?, checked_add(self.x, dx)?))
-                        })
-                        .find(|maybe_state| *maybe_state != Some(&SeatState::NotPresent))
-                        .unwrap()
-                })
-                .filter(|state| **state == SeatState::Occupied)
-                .count();
-
-            let old_state = self.previous_state[[self.y, self.x]];
-            let new_state = match (old_state, occupied_neighboring_seats) {
-                (SeatState::Empty, 0) => SeatState::Occupied,
-                (SeatState::Occupied, 5..=8) => SeatState::Empty,
-                (old_state, _) => old_state,
-            };
-
-            if self.x + 1 == self.previous_state.ncols() {
-                self.x = 0;
-                self.y += 1;
-            } else {
-                self.x += 1;
-            }
+/// Draws the stable seating layout of the first set of rules, empty seats in green and occupied
+/// seats in red.
+pub fn render_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    let mut seating = seating_automaton(parse_input(input_lines));
 
-            Some(new_state)
-        } else {
-            None
-        }
-    }
+    seating.run_until_stable();
+
+    Raster::from_grid(seating.cells().view(), seat_color)
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let mut seat_states = parse_input(input_lines);
+/// Draws the stable seating layout of the tweaked rules, like [`render_puzzle1`].
+pub fn render_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    let mut seating = tweaked_seating_automaton(parse_input(input_lines));
 
-    loop {
-        let previous_states = seat_states.clone();
-        seat_states
-            .iter_mut()
-            .zip(TweakedStateUpdateIter::new(&previous_states))
-            .for_each(|(target, src)| *target = src);
+    seating.run_until_stable();
 
-        if seat_states == previous_states {
-            break;
-        }
-    }
+    Raster::from_grid(seating.cells().view(), seat_color)
+}
 
-    seat_states
-        .iter()
-        .filter(|s| **s == SeatState::Occupied)
-        .count()
-        .to_string()
+/// Yields the seating layout of the first set of rules after each round, until it is stable.
+pub fn animation_frames<I: Iterator<Item = String>>(
+    input_lines: I,
+) -> impl Iterator<Item = Raster> {
+    let mut seating = Some(seating_automaton(parse_input(input_lines)));
+
+    std::iter::from_fn(move || {
+        let current = seating.as_mut()?;
+        let frame = Raster::from_grid(current.cells().view(), seat_color);
+        if !current.step() {
+            seating = None;
+        }
+        Some(frame)
+    })
 }
 
 #[cfg(test)]
@@ -339,52 +276,37 @@ mod tests {
     }
 
     #[test]
-    fn test_state_update_iter0() {
-        let mut current_state = STATE_ARRAYS[0].clone();
-        current_state
-            .iter_mut()
-            .zip(StateUpdateIter::new(&STATE_ARRAYS[0]))
-            .for_each(|(target, src)| *target = src);
-        assert_eq!(state_array_to_string(&current_state), STATE_STRS[1]);
+    fn test_seating_step0() {
+        let mut seating = seating_automaton(STATE_ARRAYS[0].clone());
+        seating.step();
+        assert_eq!(state_array_to_string(seating.cells()), STATE_STRS[1]);
     }
 
     #[test]
-    fn test_state_update_iter1() {
-        let mut current_state = STATE_ARRAYS[1].clone();
-        current_state
-            .iter_mut()
-            .zip(StateUpdateIter::new(&STATE_ARRAYS[1]))
-            .for_each(|(target, src)| *target = src);
-        assert_eq!(state_array_to_string(&current_state), STATE_STRS[2]);
+    fn test_seating_step1() {
+        let mut seating = seating_automaton(STATE_ARRAYS[1].clone());
+        seating.step();
+        assert_eq!(state_array_to_string(seating.cells()), STATE_STRS[2]);
     }
 
     #[test]
-    fn test_state_update_iter2() {
-        let mut current_state = STATE_ARRAYS[2].clone();
-        current_state
-            .iter_mut()
-            .zip(StateUpdateIter::new(&STATE_ARRAYS[2]))
-            .for_each(|(target, src)| *target = src);
-        assert_eq!(state_array_to_string(&current_state), STATE_STRS[3]);
+    fn test_seating_step2() {
+        let mut seating = seating_automaton(STATE_ARRAYS[2].clone());
+        seating.step();
+        assert_eq!(state_array_to_string(seating.cells()), STATE_STRS[3]);
     }
 
     #[test]
-    fn test_state_update_iter3() {
-        let mut current_state = STATE_ARRAYS[3].clone();
-        current_state
-            .iter_mut()
-            .zip(StateUpdateIter::new(&STATE_ARRAYS[3]))
-            .for_each(|(target, src)| *target = src);
-        assert_eq!(state_array_to_string(&current_state), STATE_STRS[4]);
+    fn test_seating_step3() {
+        let mut seating = seating_automaton(STATE_ARRAYS[3].clone());
+        seating.step();
+        assert_eq!(state_array_to_string(seating.cells()), STATE_STRS[4]);
     }
 
     #[test]
-    fn test_state_update_iter4() {
-        let mut current_state = STATE_ARRAYS[4].clone();
-        current_state
-            .iter_mut()
-            .zip(StateUpdateIter::new(&STATE_ARRAYS[4]))
-            .for_each(|(target, src)| *target = src);
-        assert_eq!(state_array_to_string(&current_state), STATE_STRS[5]);
+    fn test_seating_step4() {
+        let mut seating = seating_automaton(STATE_ARRAYS[4].clone());
+        seating.step();
+        assert_eq!(state_array_to_string(seating.cells()), STATE_STRS[5]);
     }
 }
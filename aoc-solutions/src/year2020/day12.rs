@@ -1,57 +1,140 @@
 use std::convert::{TryFrom, TryInto};
 use std::default::Default;
+use std::fmt::{self, Display, Write};
 use std::str::FromStr;
 
-use cgmath::{Basis2, Deg, InnerSpace, Point2, Rad, Rotation, Rotation2, Vector2};
+use cgmath::{EuclideanSpace, Point2, Vector2};
+use itertools::Itertools;
 
-#[derive(Debug, Clone, Copy)]
-struct Ship {
-    /// An angle in radians, where 0 is east. A positive value implies a CCW-rotation.
-    facing_direction: Rad<f64>,
-    position: Point2<f64>,
+/// Rotates `vector` counter-clockwise by `quarter_turns` times 90°.
+fn rotate_quarter_turns(vector: Vector2<i64>, quarter_turns: u8) -> Vector2<i64> {
+    match quarter_turns % 4 {
+        0 => vector,
+        1 => Vector2::new(-vector.y, vector.x),
+        2 => Vector2::new(-vector.x, -vector.y),
+        3 => Vector2::new(vector.y, -vector.x),
+        _ => unreachable!(),
+    }
+}
+
+/// Like [`rotate_quarter_turns`], but returns `None` if a component cannot be negated.
+fn checked_rotate_quarter_turns(vector: Vector2<i64>, quarter_turns: u8) -> Option<Vector2<i64>> {
+    Some(match quarter_turns % 4 {
+        0 => vector,
+        1 => Vector2::new(vector.y.checked_neg()?, vector.x),
+        2 => Vector2::new(vector.x.checked_neg()?, vector.y.checked_neg()?),
+        3 => Vector2::new(vector.y, vector.x.checked_neg()?),
+        _ => unreachable!(),
+    })
+}
+
+/// Computes `a + b * factor`, returning `None` on overflow.
+fn checked_scaled_add(a: Vector2<i64>, b: Vector2<i64>, factor: i64) -> Option<Vector2<i64>> {
+    Some(Vector2::new(
+        a.x.checked_add(b.x.checked_mul(factor)?)?,
+        a.y.checked_add(b.y.checked_mul(factor)?)?,
+    ))
+}
+
+/// Something that can be steered with [`NavInstruction`]s.
+pub trait Navigate: Sized {
+    fn position(&self) -> Point2<i64>;
+
+    fn exec_instruction(&mut self, nav_instruction: NavInstruction);
+
+    /// Like [`Navigate::exec_instruction`], but returns the resulting state instead of modifying
+    /// `self`, or `None` if any coordinate would overflow.
+    fn checked_exec_instruction(&self, nav_instruction: NavInstruction) -> Option<Self>;
+
+    /// The absolute position of the waypoint the ship is steered by, if it uses one.
+    fn waypoint(&self) -> Option<Point2<i64>> {
+        None
+    }
+
+    fn manhattan_distance(&self) -> u64 {
+        let position = self.position();
+        position.x.unsigned_abs() + position.y.unsigned_abs()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ship {
+    /// A unit vector, where `(1, 0)` is east and `(0, 1)` is north.
+    facing_direction: Vector2<i64>,
+    position: Point2<i64>,
 }
 
 impl Ship {
-    pub fn exec_instruction(&mut self, nav_instruction: NavInstruction) {
+    pub const fn facing_direction(&self) -> Vector2<i64> {
+        self.facing_direction
+    }
+}
+
+impl Navigate for Ship {
+    fn position(&self) -> Point2<i64> {
+        self.position
+    }
+
+    fn exec_instruction(&mut self, nav_instruction: NavInstruction) {
         match nav_instruction {
             NavInstruction::Move {
                 direction,
                 distance,
-            } => self.move_in_direction(distance, direction),
-            NavInstruction::Turn { direction, angle } => {
-                self.facing_direction += match direction {
-                    TurnDirection::Left => angle,
-                    TurnDirection::Right => -angle,
-                }
+            } => {
+                let move_dir = direction.unit_vector().unwrap_or(self.facing_direction);
+                self.position += move_dir * distance;
+            }
+            NavInstruction::Turn {
+                direction,
+                quarter_turns,
+            } => {
+                self.facing_direction = rotate_quarter_turns(
+                    self.facing_direction,
+                    direction.ccw_quarter_turns(quarter_turns),
+                )
             }
         }
     }
 
-    fn move_in_direction(&mut self, distance: f64, direction: MoveDirection) {
-        let move_dir_radians = match direction {
-            MoveDirection::North => Deg(90f64).into(),
-            MoveDirection::East => Deg(0f64).into(),
-            MoveDirection::South => Deg(-90f64).into(),
-            MoveDirection::West => Deg(180f64).into(),
-            MoveDirection::Forward => self.facing_direction,
-        };
-
-        self.position = self.position
-            + Basis2::from_angle(move_dir_radians).rotate_vector(Vector2::from([distance, 0.0]));
+    fn checked_exec_instruction(&self, nav_instruction: NavInstruction) -> Option<Self> {
+        let mut ship = *self;
+        match nav_instruction {
+            NavInstruction::Move {
+                direction,
+                distance,
+            } => {
+                let move_dir = direction.unit_vector().unwrap_or(self.facing_direction);
+                ship.position = Point2::from_vec(checked_scaled_add(
+                    self.position.to_vec(),
+                    move_dir,
+                    distance,
+                )?);
+            }
+            NavInstruction::Turn {
+                direction,
+                quarter_turns,
+            } => {
+                ship.facing_direction = checked_rotate_quarter_turns(
+                    self.facing_direction,
+                    direction.ccw_quarter_turns(quarter_turns),
+                )?
+            }
+        }
+        Some(ship)
     }
 }
 
 impl Default for Ship {
     fn default() -> Self {
         Self {
-            facing_direction: Rad(0.0),
-            position: Point2::from([0.0, 0.0]),
+            facing_direction: Vector2::new(1, 0),
+            position: Point2::new(0, 0),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum MoveDirection {
+pub enum MoveDirection {
     North,
     East,
     South,
@@ -59,6 +142,20 @@ enum MoveDirection {
     Forward,
 }
 
+impl MoveDirection {
+    /// The unit vector of this direction or `None` for [`MoveDirection::Forward`], which depends on
+    /// the ship.
+    pub const fn unit_vector(self) -> Option<Vector2<i64>> {
+        match self {
+            MoveDirection::North => Some(Vector2::new(0, 1)),
+            MoveDirection::East => Some(Vector2::new(1, 0)),
+            MoveDirection::South => Some(Vector2::new(0, -1)),
+            MoveDirection::West => Some(Vector2::new(-1, 0)),
+            MoveDirection::Forward => None,
+        }
+    }
+}
+
 impl TryFrom<u8> for MoveDirection {
     type Error = &'static str;
 
@@ -75,11 +172,22 @@ impl TryFrom<u8> for MoveDirection {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum TurnDirection {
+pub enum TurnDirection {
     Left,
     Right,
 }
 
+impl TurnDirection {
+    /// Converts a turn by `quarter_turns` in this direction into the equivalent number of
+    /// counter-clockwise quarter turns (between 0 and 3).
+    pub const fn ccw_quarter_turns(self, quarter_turns: u8) -> u8 {
+        match self {
+            TurnDirection::Left => quarter_turns % 4,
+            TurnDirection::Right => (4 - quarter_turns % 4) % 4,
+        }
+    }
+}
+
 impl TryFrom<u8> for TurnDirection {
     type Error = &'static str;
 
@@ -92,113 +200,377 @@ impl TryFrom<u8> for TurnDirection {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum NavInstruction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavInstruction {
     Move {
         direction: MoveDirection,
-        distance: f64,
+        distance: i64,
     },
     Turn {
         direction: TurnDirection,
-        /// An angle in radians, where 0 is east. A positive value implies a CCW-rotation.
-        angle: Rad<f64>,
+        /// The angle of the turn in multiples of 90°, reduced to be between 0 and 3.
+        quarter_turns: u8,
     },
 }
 
+impl Display for NavInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            NavInstruction::Move {
+                direction,
+                distance,
+            } => {
+                let direction_char = match direction {
+                    MoveDirection::North => 'N',
+                    MoveDirection::East => 'E',
+                    MoveDirection::South => 'S',
+                    MoveDirection::West => 'W',
+                    MoveDirection::Forward => 'F',
+                };
+                write!(f, "{direction_char}{distance}")
+            }
+            NavInstruction::Turn {
+                direction,
+                quarter_turns,
+            } => {
+                let direction_char = match direction {
+                    TurnDirection::Left => 'L',
+                    TurnDirection::Right => 'R',
+                };
+                write!(f, "{direction_char}{}", quarter_turns as u32 * 90)
+            }
+        }
+    }
+}
+
 impl FromStr for NavInstruction {
     type Err = &'static str;
 
     fn from_str(nav_instruction_str: &str) -> std::result::Result<Self, <Self as FromStr>::Err> {
-        Ok(
-            if let Ok(move_dir) = nav_instruction_str.as_bytes()[0].try_into() {
-                Self::Move {
-                    direction: move_dir,
-                    distance: nav_instruction_str[1..]
-                        .parse()
-                        .map_err(|_| "Invalid move instruction str")?,
-                }
-            } else if let Ok(turn_dir) = nav_instruction_str.as_bytes()[0].try_into() {
-                Self::Turn {
-                    direction: turn_dir,
-                    angle: nav_instruction_str[1..]
-                        .parse::<f64>()
-                        .map(Deg)
-                        .map(Rad::from)
-                        .map_err(|_| "Invalid turn instruction str")?,
-                }
-            } else {
-                Err("Invalid navigation instruction str")?
-            },
+        let first_byte = *nav_instruction_str
+            .as_bytes()
+            .first()
+            .ok_or("Empty navigation instruction str")?;
+        Ok(if let Ok(move_dir) = first_byte.try_into() {
+            Self::Move {
+                direction: move_dir,
+                distance: nav_instruction_str[1..]
+                    .parse()
+                    .map_err(|_| "Invalid move instruction str")?,
+            }
+        } else if let Ok(turn_dir) = first_byte.try_into() {
+            let angle: u32 = nav_instruction_str[1..]
+                .parse()
+                .map_err(|_| "Invalid turn instruction str")?;
+            if angle % 90 != 0 {
+                Err("Turn angle is not a multiple of 90 degrees")?
+            }
+            Self::Turn {
+                direction: turn_dir,
+                quarter_turns: (angle / 90 % 4) as u8,
+            }
+        } else {
+            Err("Invalid navigation instruction str")?
+        })
+    }
+}
+
+/// The error returned by [`checked_trajectory`] if a coordinate overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError {
+    pub instruction_index: usize,
+    pub nav_instruction: NavInstruction,
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Coordinate overflow while executing navigation instruction {} ({:?})",
+            self.instruction_index, self.nav_instruction
         )
     }
 }
 
-fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> impl Iterator<Item = NavInstruction> {
+impl std::error::Error for OverflowError {}
+
+/// Replays `nav_instructions` on `ship` with overflow checks.
+///
+/// The returned states start with `ship` itself, followed by the state after each instruction.
+pub fn checked_trajectory<S, I>(ship: S, nav_instructions: I) -> Result<Vec<S>, OverflowError>
+where
+    S: Navigate + Copy,
+    I: IntoIterator<Item = NavInstruction>,
+{
+    let mut trajectory = vec![ship];
+    for (instruction_index, nav_instruction) in nav_instructions.into_iter().enumerate() {
+        let next = trajectory
+            .last()
+            .unwrap()
+            .checked_exec_instruction(nav_instruction)
+            .ok_or(OverflowError {
+                instruction_index,
+                nav_instruction,
+            })?;
+        trajectory.push(next);
+    }
+    Ok(trajectory)
+}
+
+/// The states of a ship after each of a list of [`NavInstruction`]s, which can be exported for
+/// plotting.
+#[derive(Debug, Clone)]
+pub struct Route<S> {
+    nav_instructions: Vec<NavInstruction>,
+    /// The initial state, followed by the state after each instruction.
+    states: Vec<S>,
+}
+
+impl<S: Navigate + Copy> Route<S> {
+    pub fn checked_replay<I: IntoIterator<Item = NavInstruction>>(
+        ship: S,
+        nav_instructions: I,
+    ) -> Result<Self, OverflowError> {
+        let nav_instructions: Vec<_> = nav_instructions.into_iter().collect();
+        let states = checked_trajectory(ship, nav_instructions.iter().copied())?;
+        Ok(Self {
+            nav_instructions,
+            states,
+        })
+    }
+
+    pub fn nav_instructions(&self) -> &[NavInstruction] {
+        &self.nav_instructions
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// Renders the route as CSV, with one row for the initial state and one for each instruction.
+    ///
+    /// The waypoint columns are left empty for ships without a waypoint.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "step,instruction,ship_x,ship_y,waypoint_x,waypoint_y,manhattan_distance\n",
+        );
+        let instructions = std::iter::once(None).chain(self.nav_instructions.iter().map(Some));
+        for (step, (state, nav_instruction)) in self.states.iter().zip(instructions).enumerate() {
+            let position = state.position();
+            let instruction = nav_instruction.map(ToString::to_string).unwrap_or_default();
+            let (waypoint_x, waypoint_y) = state
+                .waypoint()
+                .map(|waypoint| (waypoint.x.to_string(), waypoint.y.to_string()))
+                .unwrap_or_default();
+            writeln!(
+                csv,
+                "{step},{instruction},{},{},{waypoint_x},{waypoint_y},{}",
+                position.x,
+                position.y,
+                state.manhattan_distance()
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// Renders the route of the ship (and its waypoint, if any) as SVG polylines, with north
+    /// pointing up and the final Manhattan distance as a caption.
+    pub fn to_svg(&self) -> String {
+        const SIZE: f64 = 800.0;
+        const MARGIN: f64 = 20.0;
+        const CAPTION_HEIGHT: f64 = 30.0;
+
+        let ship_positions: Vec<_> = self.states.iter().map(Navigate::position).collect();
+        let waypoint_positions: Vec<_> =
+            self.states.iter().filter_map(Navigate::waypoint).collect();
+
+        let all_positions = ship_positions.iter().chain(&waypoint_positions);
+        let min_x = all_positions.clone().map(|p| p.x).min().unwrap_or(0);
+        let max_x = all_positions.clone().map(|p| p.x).max().unwrap_or(0);
+        let min_y = all_positions.clone().map(|p| p.y).min().unwrap_or(0);
+        let max_y = all_positions.map(|p| p.y).max().unwrap_or(0);
+
+        let extent = (max_x as f64 - min_x as f64)
+            .max(max_y as f64 - min_y as f64)
+            .max(1.0);
+        let scale = (SIZE - 2.0 * MARGIN) / extent;
+        let to_canvas = |p: &Point2<i64>| {
+            (
+                MARGIN + (p.x as f64 - min_x as f64) * scale,
+                MARGIN + (max_y as f64 - p.y as f64) * scale,
+            )
+        };
+        let polyline_points = |positions: &[Point2<i64>]| {
+            positions
+                .iter()
+                .map(|p| {
+                    let (x, y) = to_canvas(p);
+                    format!("{x:.2},{y:.2}")
+                })
+                .join(" ")
+        };
+
+        let width = 2.0 * MARGIN + (max_x as f64 - min_x as f64) * scale;
+        let height = 2.0 * MARGIN + (max_y as f64 - min_y as f64) * scale;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{:.0}" viewBox="0 0 {width:.2} {:.2}">"#,
+            height + CAPTION_HEIGHT,
+            height + CAPTION_HEIGHT,
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        if !waypoint_positions.is_empty() {
+            writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="darkorange" stroke-width="1" stroke-dasharray="4 2"><title>waypoint</title></polyline>"#,
+                polyline_points(&waypoint_positions)
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="steelblue" stroke-width="2"><title>ship</title></polyline>"#,
+            polyline_points(&ship_positions)
+        )
+        .unwrap();
+        if let (Some(start), Some(end)) = (ship_positions.first(), ship_positions.last()) {
+            let (start_x, start_y) = to_canvas(start);
+            let (end_x, end_y) = to_canvas(end);
+            writeln!(
+                svg,
+                r#"<circle cx="{start_x:.2}" cy="{start_y:.2}" r="4" fill="green"><title>start</title></circle>"#
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r#"<circle cx="{end_x:.2}" cy="{end_y:.2}" r="4" fill="red"><title>end</title></circle>"#
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<text x="{MARGIN}" y="{:.2}" font-family="sans-serif" font-size="16">Manhattan distance: {}</text>"#,
+            height + CAPTION_HEIGHT / 2.0 + 6.0,
+            self.states.last().map_or(0, Navigate::manhattan_distance)
+        )
+        .unwrap();
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+pub fn parse_input<I: Iterator<Item = String>>(
+    input_lines: I,
+) -> impl Iterator<Item = NavInstruction> {
     input_lines.map(|line| line.parse().unwrap())
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
     let nav_instructions = parse_input(input_lines);
 
-    let final_pos = nav_instructions
+    nav_instructions
         .fold(Ship::default(), |mut ship, nav_instruction| {
             ship.exec_instruction(nav_instruction);
             ship
         })
-        .position;
-    (final_pos.x.abs() + final_pos.y.abs()).to_string()
+        .manhattan_distance()
+        .to_string()
 }
 
-#[derive(Debug, Clone, Copy)]
-struct ShipV2 {
-    /// An angle in radians, where 0 is east. A positive value implies a CCW-rotation.
-    position: Point2<f64>,
-    waypoint_rel_pos: Vector2<f64>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShipV2 {
+    position: Point2<i64>,
+    /// The position of the waypoint relative to the ship.
+    waypoint_rel_pos: Vector2<i64>,
 }
 
 impl ShipV2 {
-    pub fn exec_instruction(&mut self, nav_instruction: NavInstruction) {
+    pub const fn waypoint_rel_pos(&self) -> Vector2<i64> {
+        self.waypoint_rel_pos
+    }
+
+    fn move_waypoint(&mut self, distance: i64, direction: MoveDirection) {
+        self.waypoint_rel_pos += direction
+            .unit_vector()
+            .expect("Cannot move waypoint forward")
+            * distance;
+    }
+}
+
+impl Navigate for ShipV2 {
+    fn position(&self) -> Point2<i64> {
+        self.position
+    }
+
+    fn waypoint(&self) -> Option<Point2<i64>> {
+        Some(self.position + self.waypoint_rel_pos)
+    }
+
+    fn exec_instruction(&mut self, nav_instruction: NavInstruction) {
         match nav_instruction {
             NavInstruction::Move {
                 direction: MoveDirection::Forward,
                 distance,
-            } => {
-                self.position = self.position
-                    + self.waypoint_rel_pos / self.waypoint_rel_pos.magnitude2() * distance
-            }
+            } => self.position += self.waypoint_rel_pos * distance,
             NavInstruction::Move {
                 direction,
                 distance,
             } => self.move_waypoint(distance, direction),
-            NavInstruction::Turn { direction, angle } => {
-                self.rotate_waypoint_around_ship(match direction {
-                    TurnDirection::Left => angle,
-                    TurnDirection::Right => -angle,
-                })
+            NavInstruction::Turn {
+                direction,
+                quarter_turns,
+            } => {
+                self.waypoint_rel_pos = rotate_quarter_turns(
+                    self.waypoint_rel_pos,
+                    direction.ccw_quarter_turns(quarter_turns),
+                )
             }
         }
     }
 
-    fn rotate_waypoint_around_ship<R: Into<Rad<f64>>>(&mut self, angle_rad: R) {
-        self.waypoint_rel_pos = Basis2::from_angle(angle_rad).rotate_vector(self.waypoint_rel_pos);
-    }
-
-    fn move_waypoint(&mut self, distance: f64, direction: MoveDirection) {
-        self.waypoint_rel_pos += match direction {
-            MoveDirection::North => Vector2::from([0.0, distance]),
-            MoveDirection::East => Vector2::from([distance, 0.0]),
-            MoveDirection::South => Vector2::from([0.0, -distance]),
-            MoveDirection::West => Vector2::from([-distance, 0.0]),
-            MoveDirection::Forward => panic!("Cannot move waypoint forward"),
-        };
+    fn checked_exec_instruction(&self, nav_instruction: NavInstruction) -> Option<Self> {
+        let mut ship = *self;
+        match nav_instruction {
+            NavInstruction::Move {
+                direction: MoveDirection::Forward,
+                distance,
+            } => {
+                ship.position = Point2::from_vec(checked_scaled_add(
+                    self.position.to_vec(),
+                    self.waypoint_rel_pos,
+                    distance,
+                )?)
+            }
+            NavInstruction::Move {
+                direction,
+                distance,
+            } => {
+                ship.waypoint_rel_pos =
+                    checked_scaled_add(self.waypoint_rel_pos, direction.unit_vector()?, distance)?
+            }
+            NavInstruction::Turn {
+                direction,
+                quarter_turns,
+            } => {
+                ship.waypoint_rel_pos = checked_rotate_quarter_turns(
+                    self.waypoint_rel_pos,
+                    direction.ccw_quarter_turns(quarter_turns),
+                )?
+            }
+        }
+        Some(ship)
     }
 }
 
 impl Default for ShipV2 {
     fn default() -> Self {
         Self {
-            position: Point2::from([0.0, 0.0]),
-            waypoint_rel_pos: Vector2::from([10.0, 1.0]),
+            position: Point2::new(0, 0),
+            waypoint_rel_pos: Vector2::new(10, 1),
         }
     }
 }
@@ -206,11 +578,95 @@ impl Default for ShipV2 {
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let nav_instructions = parse_input(input_lines);
 
-    let final_pos = nav_instructions
+    nav_instructions
         .fold(ShipV2::default(), |mut ship, nav_instruction| {
             ship.exec_instruction(nav_instruction);
             ship
         })
-        .position;
-    (final_pos.x.abs() + final_pos.y.abs()).to_string()
+        .manhattan_distance()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const EXAMPLE_INSTRUCTIONS: [&str; 5] = ["F10", "N3", "F7", "R90", "F11"];
+
+    fn example_input() -> impl Iterator<Item = String> {
+        EXAMPLE_INSTRUCTIONS.iter().map(|&s| s.to_owned())
+    }
+
+    #[test]
+    fn test_solve_puzzle1() {
+        assert_eq!(solve_puzzle1(example_input()), "25");
+    }
+
+    #[test]
+    fn test_solve_puzzle2() {
+        assert_eq!(solve_puzzle2(example_input()), "286");
+    }
+
+    #[test]
+    fn test_turn_not_multiple_of_90() {
+        assert_eq!(
+            "L45".parse::<NavInstruction>(),
+            Err("Turn angle is not a multiple of 90 degrees")
+        );
+    }
+
+    #[test]
+    fn test_checked_trajectory() {
+        let trajectory =
+            checked_trajectory(ShipV2::default(), parse_input(example_input())).unwrap();
+        assert_eq!(
+            trajectory
+                .iter()
+                .map(|ship| ship.position())
+                .collect::<Vec<_>>(),
+            vec![
+                Point2::new(0, 0),
+                Point2::new(100, 10),
+                Point2::new(100, 10),
+                Point2::new(170, 38),
+                Point2::new(170, 38),
+                Point2::new(214, -72),
+            ]
+        );
+        assert_eq!(
+            checked_trajectory(
+                Ship::default(),
+                [NavInstruction::Move {
+                    direction: MoveDirection::East,
+                    distance: i64::MAX,
+                }; 2]
+            ),
+            Err(OverflowError {
+                instruction_index: 1,
+                nav_instruction: NavInstruction::Move {
+                    direction: MoveDirection::East,
+                    distance: i64::MAX,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_route_csv() {
+        let route = Route::checked_replay(ShipV2::default(), parse_input(example_input())).unwrap();
+        assert_eq!(
+            route.to_csv(),
+            indoc! {"
+                step,instruction,ship_x,ship_y,waypoint_x,waypoint_y,manhattan_distance
+                0,,0,0,10,1,0
+                1,F10,100,10,110,11,110
+                2,N3,100,10,110,14,110
+                3,F7,170,38,180,42,208
+                4,R90,170,38,174,28,208
+                5,F11,214,-72,218,-82,286
+            "}
+        );
+    }
 }
@@ -1,43 +1,45 @@
-use itertools::Itertools;
+use crate::params::Parameters;
+use crate::windows;
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let depths: Vec<u32> = input_lines
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<u32> {
+    input_lines
         .map(|l| {
             l.parse()
                 .expect("Input was not solely lines of unsigned integers")
         })
-        .collect();
+        .collect()
+}
 
-    let solution = depths
-        .iter()
-        .copied()
-        .tuple_windows()
-        .map(|(prev_depth, cur_depth)| i64::from(cur_depth) - i64::from(prev_depth))
-        .filter(|&depth_change| depth_change > 0)
-        .count();
+/// Counts the increases of the sums of `width` consecutive depths, by default of single depths.
+pub fn solve_puzzle1_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    params.expect_only(&["width"]);
+    let depths = parse_input(input_lines);
+
+    let solution = windows::count_sum_increases(&depths, params.get_or("width", 1));
 
     solution.to_string()
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let depths: Vec<u32> = input_lines
-        .map(|l| {
-            l.parse()
-                .expect("Input was not solely lines of unsigned integers")
-        })
-        .collect();
-
-    let solution = depths
-        .iter()
-        .copied()
-        .tuple_windows()
-        .map(|(depth0, depth1, depth2)| depth0 + depth1 + depth2)
-        .tuple_windows()
-        .map(|(prev_3depth_sum, cur_3depth_sum)| {
-            i64::from(cur_3depth_sum) - i64::from(prev_3depth_sum)
-        })
-        .filter(|&depth_sum_change| depth_sum_change > 0)
-        .count();
+/// Counts the increases of the sums of `width` consecutive depths, by default of three depths.
+pub fn solve_puzzle2_with<I: Iterator<Item = String>>(
+    input_lines: I,
+    params: &Parameters,
+) -> String {
+    params.expect_only(&["width"]);
+    let depths = parse_input(input_lines);
+
+    let solution = windows::count_sum_increases(&depths, params.get_or("width", 3));
 
     solution.to_string()
 }
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle1_with(input_lines, &Parameters::default())
+}
+
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    solve_puzzle2_with(input_lines, &Parameters::default())
+}
@@ -1,10 +1,10 @@
 use std::str::FromStr;
 
-#[derive(Clone, Copy)]
-enum SubmarineCommand {
-    Forward(u32),
-    Down(u32),
-    Up(u32),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmarineCommand {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
 }
 
 impl FromStr for SubmarineCommand {
@@ -12,70 +12,232 @@ impl FromStr for SubmarineCommand {
 
     fn from_str(cmd_str: &str) -> Result<Self, <Self as FromStr>::Err> {
         match cmd_str.split_once(' ') {
-            Some(("forward", units_str)) => {
-                Ok(Self::Forward(units_str.parse().map_err(|_| {
-                    "expected unsigned integer after `forward`"
-                })?))
-            }
+            Some(("forward", units_str)) => Ok(Self::Forward(
+                units_str
+                    .parse()
+                    .map_err(|_| "expected integer after `forward`")?,
+            )),
             Some(("down", units_str)) => Ok(Self::Down(
                 units_str
                     .parse()
-                    .map_err(|_| "expected unsigned integer after `down`")?,
+                    .map_err(|_| "expected integer after `down`")?,
             )),
             Some(("up", units_str)) => Ok(Self::Up(
                 units_str
                     .parse()
-                    .map_err(|_| "expected unsigned integer after `up`")?,
+                    .map_err(|_| "expected integer after `up`")?,
             )),
             _ => Err("expected one of `forward <units>`, `down <units>`, `up <units>`"),
         }
     }
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let depths: Vec<SubmarineCommand> = input_lines
+/// Where the submarine is and where it is aiming, with the depth growing downwards, so that
+/// negative depths are above the surface.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubmarineState {
+    pub horizontal_pos: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+/// An interpretation of the commands of the planned course.
+///
+/// Besides the two interpretations of the puzzle, [`DirectSubmarine`] and [`AimingSubmarine`],
+/// models may bring their own commands.
+pub trait Submarine {
+    type Command;
+
+    /// The state after executing the command in the given state.
+    fn execute(&self, state: SubmarineState, command: &Self::Command) -> SubmarineState;
+
+    /// The states from the start at the surface through each of the commands.
+    fn trajectory(&self, commands: &[Self::Command]) -> Vec<SubmarineState> {
+        let mut states = Vec::with_capacity(commands.len() + 1);
+        states.push(SubmarineState::default());
+        for command in commands {
+            states.push(self.execute(*states.last().unwrap(), command));
+        }
+        states
+    }
+
+    fn final_state(&self, commands: &[Self::Command]) -> SubmarineState {
+        commands
+            .iter()
+            .fold(SubmarineState::default(), |state, command| {
+                self.execute(state, command)
+            })
+    }
+}
+
+/// The interpretation of the first puzzle: `down` and `up` change the depth directly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectSubmarine;
+
+impl Submarine for DirectSubmarine {
+    type Command = SubmarineCommand;
+
+    fn execute(&self, state: SubmarineState, command: &SubmarineCommand) -> SubmarineState {
+        match *command {
+            SubmarineCommand::Forward(units) => SubmarineState {
+                horizontal_pos: state.horizontal_pos + units,
+                ..state
+            },
+            SubmarineCommand::Down(units) => SubmarineState {
+                depth: state.depth + units,
+                ..state
+            },
+            SubmarineCommand::Up(units) => SubmarineState {
+                depth: state.depth - units,
+                ..state
+            },
+        }
+    }
+}
+
+/// The interpretation of the second puzzle: `down` and `up` change the aim, and `forward` also
+/// changes the depth by the aim for each unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AimingSubmarine;
+
+impl Submarine for AimingSubmarine {
+    type Command = SubmarineCommand;
+
+    fn execute(&self, state: SubmarineState, command: &SubmarineCommand) -> SubmarineState {
+        match *command {
+            SubmarineCommand::Forward(units) => SubmarineState {
+                horizontal_pos: state.horizontal_pos + units,
+                depth: state.depth + state.aim * units,
+                ..state
+            },
+            SubmarineCommand::Down(units) => SubmarineState {
+                aim: state.aim + units,
+                ..state
+            },
+            SubmarineCommand::Up(units) => SubmarineState {
+                aim: state.aim - units,
+                ..state
+            },
+        }
+    }
+}
+
+fn parse_input<C: FromStr, I: Iterator<Item = String>>(input_lines: I) -> Vec<C> {
+    input_lines
         .map(|l| {
-            l.parse()
-                .expect("Input was not solely lines of command, number of units pairs")
+            l.parse().unwrap_or_else(|_| {
+                panic!("Input was not solely lines of command, number of units pairs")
+            })
         })
-        .collect();
+        .collect()
+}
 
-    let (final_depth, final_horizontal_pos) =
-        depths
-            .iter()
-            .copied()
-            .fold((0, 0), |(depth, horizontal_pos), cmd| match cmd {
-                SubmarineCommand::Forward(units) => (depth, horizontal_pos + units),
-                SubmarineCommand::Down(units) => (depth + units, horizontal_pos),
-                SubmarineCommand::Up(units) => (depth - units, horizontal_pos),
-            });
+fn multiply_final_position<S: Submarine, I: Iterator<Item = String>>(
+    submarine: S,
+    input_lines: I,
+) -> String
+where
+    S::Command: FromStr,
+{
+    let commands: Vec<S::Command> = parse_input(input_lines);
+    let final_state = submarine.final_state(&commands);
 
-    let solution = final_depth * final_horizontal_pos;
+    let solution = final_state.depth * final_state.horizontal_pos;
 
     solution.to_string()
 }
 
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    multiply_final_position(DirectSubmarine, input_lines)
+}
+
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let depths: Vec<SubmarineCommand> = input_lines
-        .map(|l| {
-            l.parse()
-                .expect("Input was not solely lines of unsigned integers")
-        })
-        .collect();
+    multiply_final_position(AimingSubmarine, input_lines)
+}
 
-    let (final_depth, final_horizontal_pos, _final_aim) =
-        depths
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_commands() -> Vec<SubmarineCommand> {
+        parse_input(
+            [
+                "forward 5",
+                "down 5",
+                "forward 8",
+                "up 3",
+                "down 8",
+                "forward 2",
+            ]
             .iter()
-            .copied()
-            .fold((0, 0, 0), |(depth, horizontal_pos, aim), cmd| match cmd {
-                SubmarineCommand::Forward(units) => {
-                    (depth + aim * units, horizontal_pos + units, aim)
-                }
-                SubmarineCommand::Down(units) => (depth, horizontal_pos, aim + units),
-                SubmarineCommand::Up(units) => (depth, horizontal_pos, aim - units),
-            });
+            .map(|s| s.to_string()),
+        )
+    }
 
-    let solution = final_depth * final_horizontal_pos;
+    #[test]
+    fn test_official_models() {
+        let commands = example_commands();
+        let final_state = DirectSubmarine.final_state(&commands);
+        assert_eq!((final_state.horizontal_pos, final_state.depth), (15, 10));
 
-    solution.to_string()
+        let trajectory = AimingSubmarine.trajectory(&commands);
+        assert_eq!(trajectory.len(), 7);
+        assert_eq!(
+            trajectory[3],
+            SubmarineState {
+                horizontal_pos: 13,
+                depth: 40,
+                aim: 5
+            }
+        );
+        assert_eq!(trajectory[6].horizontal_pos * trajectory[6].depth, 900);
+
+        // Going up from the surface does not underflow.
+        let above_surface = DirectSubmarine.final_state(&[SubmarineCommand::Up(3)]);
+        assert_eq!(above_surface.depth, -3);
+    }
+
+    #[test]
+    fn test_user_defined_model() {
+        /// Can also go backwards and turn around, which flips the aim.
+        enum Command {
+            Official(SubmarineCommand),
+            Back(i64),
+            Turn,
+        }
+
+        struct TurningSubmarine;
+
+        impl Submarine for TurningSubmarine {
+            type Command = Command;
+
+            fn execute(&self, state: SubmarineState, command: &Command) -> SubmarineState {
+                match *command {
+                    Command::Official(command) => AimingSubmarine.execute(state, &command),
+                    Command::Back(units) => {
+                        AimingSubmarine.execute(state, &SubmarineCommand::Forward(-units))
+                    }
+                    Command::Turn => SubmarineState {
+                        aim: -state.aim,
+                        ..state
+                    },
+                }
+            }
+        }
+
+        let commands = [
+            Command::Official(SubmarineCommand::Down(2)),
+            Command::Official(SubmarineCommand::Forward(4)),
+            Command::Turn,
+            Command::Back(1),
+        ];
+        assert_eq!(
+            TurningSubmarine.final_state(&commands),
+            SubmarineState {
+                horizontal_pos: 3,
+                depth: 10,
+                aim: -2
+            }
+        );
+    }
 }
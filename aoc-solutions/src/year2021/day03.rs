@@ -1,98 +1,207 @@
-/// Returns an array with the counts of bits set over all numbers for each bit index.
-/// Bit indices are LSB to MSB ascending.
-pub fn count_set_per_index<I: Iterator<Item = u16>>(nums: I) -> [u16; 16] {
-    nums.fold([0; 16], |mut acc, num| {
-        for i in 0usize..16 {
-            acc[i] += num >> i & 1;
+use std::fmt::{self, Display};
+
+use bitvec::prelude::*;
+
+/// A number of the diagnostic report, most significant bit first, of any width.
+pub type DiagnosticNumber = BitVec<usize, Msb0>;
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<DiagnosticNumber> {
+    let mut width = None;
+    input_lines
+        .map(|line| {
+            match width {
+                None => width = Some(line.len()),
+                Some(width) if width != line.len() => {
+                    panic!("Not all numbers of the report have the same width")
+                }
+                Some(_) => (),
+            }
+            line.bytes()
+                .map(|b| match b {
+                    b'0' => false,
+                    b'1' => true,
+                    _ => panic!("Input was not solely lines of binary integers"),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the counts of bits set over all numbers for each bit position, from the most
+/// significant bit.
+pub fn count_set_per_index(nums: &[DiagnosticNumber]) -> Vec<usize> {
+    let width = nums.first().map_or(0, |num| num.len());
+    nums.iter().fold(vec![0; width], |mut counts, num| {
+        for i in num.iter_ones() {
+            counts[i] += 1;
         }
-        acc
+        counts
     })
 }
 
-pub fn count_set_at_index<I: Iterator<Item = u16>>(nums: I, i: usize) -> usize {
-    nums.map(|num| usize::from(num >> i & 1)).sum()
+/// Decides which numbers a bit criteria filter keeps at a bit position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitCriterion {
+    /// Keeps the numbers with the most common value at the position, or with the given value if
+    /// both are equally common.
+    MostCommon { tie: bool },
+    /// Keeps the numbers with the least common value at the position, or with the given value if
+    /// both are equally common.
+    LeastCommon { tie: bool },
 }
 
-pub fn num_significant_bits(count_set_per_index: [u16; 16]) -> usize {
-    16 - count_set_per_index
-        .iter()
-        .rev()
-        .copied()
-        .enumerate()
-        .find_map(|(i, count)| if count > 0 { Some(i) } else { None })
-        .unwrap_or(16)
+impl BitCriterion {
+    pub const OXYGEN_GENERATOR: Self = BitCriterion::MostCommon { tie: true };
+    pub const CO2_SCRUBBER: Self = BitCriterion::LeastCommon { tie: false };
+
+    fn kept_value(self, num_set: usize, num_unset: usize) -> bool {
+        match self {
+            BitCriterion::MostCommon { tie } | BitCriterion::LeastCommon { tie }
+                if num_set == num_unset =>
+            {
+                tie
+            }
+            BitCriterion::MostCommon { .. } => num_set > num_unset,
+            BitCriterion::LeastCommon { .. } => num_set < num_unset,
+        }
+    }
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let diagnostic_nums: Vec<u16> = input_lines
-        .map(|l| u16::from_str_radix(&l, 2).expect("Input was not solely lines of binary integers"))
-        .collect();
+/// The numbers that remain after filtering at a bit position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterStep {
+    /// The bit position, counted from the most significant bit.
+    pub position: usize,
+    pub kept_value: bool,
+    /// The indices of the remaining numbers in the report.
+    pub remaining: Vec<usize>,
+}
 
-    let count_set_per_index = count_set_per_index(diagnostic_nums.iter().copied());
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterTrace {
+    /// A step for each bit position until only one number remains.
+    pub steps: Vec<FilterStep>,
+    /// The index of the one number that remains, `None` if several equal numbers remain after
+    /// the last bit position or the report is empty.
+    pub selected: Option<usize>,
+}
 
-    let mut gamma_rate = 0u16;
-    let mut epsilon_rate = 0u16;
+/// Filters the numbers bit position by bit position, from the most significant bit, until only
+/// one number remains.
+///
+/// A position at which all remaining numbers have the same value keeps all of them, even if the
+/// criterion asks for the value that none of them has.
+pub fn bit_criteria_filter(nums: &[DiagnosticNumber], criterion: BitCriterion) -> FilterTrace {
+    let width = nums.first().map_or(0, |num| num.len());
+    let mut remaining: Vec<usize> = (0..nums.len()).collect();
+    let mut steps = Vec::new();
 
-    let num_significant_bits = num_significant_bits(count_set_per_index);
+    let mut position = 0;
+    while remaining.len() > 1 && position < width {
+        let num_set = remaining.iter().filter(|&&i| nums[i][position]).count();
+        let num_unset = remaining.len() - num_set;
+        let kept_value = match (num_set, num_unset) {
+            (0, _) => false,
+            (_, 0) => true,
+            _ => criterion.kept_value(num_set, num_unset),
+        };
 
-    for (i, ones_count) in count_set_per_index
-        .iter()
-        .copied()
-        .take(num_significant_bits)
-        .enumerate()
-    {
-        let most_common_value = (ones_count as usize >= diagnostic_nums.len() / 2) as u16;
-        gamma_rate |= most_common_value << i;
-        epsilon_rate |= (1 - most_common_value) << i;
+        remaining.retain(|&i| nums[i][position] == kept_value);
+        steps.push(FilterStep {
+            position,
+            kept_value,
+            remaining: remaining.clone(),
+        });
+        position += 1;
     }
 
-    let solution = gamma_rate as u32 * epsilon_rate as u32;
-
-    solution.to_string()
+    FilterTrace {
+        steps,
+        selected: (remaining.len() == 1).then(|| remaining[0]),
+    }
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let diagnostic_nums: Vec<u16> = input_lines
-        .map(|l| u16::from_str_radix(&l, 2).expect("Input was not solely lines of binary integers"))
-        .collect();
-
-    let count_set_per_index = count_set_per_index(diagnostic_nums.iter().copied());
-
-    let num_significant_bits = num_significant_bits(count_set_per_index);
-
-    let mut nums_matching_most_common: Vec<u16> = diagnostic_nums.clone();
+/// A non-negative integer of any size, for the products of numbers wider than 64 bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BigUint {
+    /// The digits in base `BigUint::BASE`, least significant first.
+    digits: Vec<u64>,
+}
 
-    for i in (0..num_significant_bits).rev() {
-        let num_set: usize = count_set_at_index(nums_matching_most_common.iter().copied(), i);
-        let num_unset = nums_matching_most_common.len() - num_set;
-        let most_common_value = if num_set >= num_unset { 1 } else { 0 };
+impl BigUint {
+    const BASE: u64 = 1_000_000_000;
 
-        nums_matching_most_common.retain(|num| (num >> i & 1) == most_common_value);
+    fn from_bits(bits: &BitSlice<usize, Msb0>) -> Self {
+        let mut digits = vec![0];
+        for bit in bits.iter().by_vals() {
+            let mut carry = u64::from(bit);
+            for digit in &mut digits {
+                let doubled = *digit * 2 + carry;
+                *digit = doubled % Self::BASE;
+                carry = doubled / Self::BASE;
+            }
+            if carry > 0 {
+                digits.push(carry);
+            }
+        }
+        Self { digits }
+    }
 
-        if nums_matching_most_common.len() <= 1 {
-            break;
+    fn mul(&self, other: &Self) -> Self {
+        let mut digits = vec![0; self.digits.len() + other.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.digits.iter().enumerate() {
+                let product = digits[i + j] + a * b + carry;
+                digits[i + j] = product % Self::BASE;
+                carry = product / Self::BASE;
+            }
+            digits[i + other.digits.len()] += carry;
+        }
+        while digits.len() > 1 && digits.last() == Some(&0) {
+            digits.pop();
         }
+        Self { digits }
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = self.digits.iter().rev();
+        write!(f, "{}", digits.next().unwrap())?;
+        digits.try_for_each(|digit| write!(f, "{digit:09}"))
     }
+}
 
-    let oxygen_generator_rating = *nums_matching_most_common.first().unwrap();
+fn rating(nums: &[DiagnosticNumber], criterion: BitCriterion) -> BigUint {
+    let selected = bit_criteria_filter(nums, criterion)
+        .selected
+        .expect("The bit criteria do not single out one number");
+    BigUint::from_bits(&nums[selected])
+}
 
-    let mut nums_matching_least_common: Vec<u16> = diagnostic_nums.clone();
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let diagnostic_nums = parse_input(input_lines);
 
-    for i in (0..num_significant_bits).rev() {
-        let num_set: usize = count_set_at_index(nums_matching_least_common.iter().copied(), i);
-        let num_unset = nums_matching_least_common.len() - num_set;
-        let least_common_value = if num_unset <= num_set { 0 } else { 1 };
+    // Ties go to 1, as they do for the oxygen generator rating.
+    let gamma_rate: DiagnosticNumber = count_set_per_index(&diagnostic_nums)
+        .into_iter()
+        .map(|num_set| 2 * num_set >= diagnostic_nums.len())
+        .collect();
+    let epsilon_rate = !gamma_rate.clone();
 
-        nums_matching_least_common.retain(|num| (num >> i & 1) == least_common_value);
+    let solution = BigUint::from_bits(&gamma_rate).mul(&BigUint::from_bits(&epsilon_rate));
 
-        if nums_matching_least_common.len() <= 1 {
-            break;
-        }
-    }
+    solution.to_string()
+}
 
-    let co2_scrubber_rating = *nums_matching_least_common.first().unwrap();
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let diagnostic_nums = parse_input(input_lines);
+
+    let oxygen_generator_rating = rating(&diagnostic_nums, BitCriterion::OXYGEN_GENERATOR);
+    let co2_scrubber_rating = rating(&diagnostic_nums, BitCriterion::CO2_SCRUBBER);
 
-    let solution = oxygen_generator_rating as u32 * co2_scrubber_rating as u32;
+    let solution = oxygen_generator_rating.mul(&co2_scrubber_rating);
 
     solution.to_string()
 }
@@ -121,4 +230,44 @@ mod test {
             "230"
         );
     }
+
+    #[test]
+    fn test_ties_and_wide_numbers() {
+        // Two of three numbers are needed for a majority, not one.
+        assert_eq!(
+            solve_puzzle1(["110", "011", "000"].into_iter().map(|s| s.to_owned())),
+            "10"
+        );
+
+        let wide_nums = [
+            format!("11{}", "0".repeat(68)),
+            format!("10{}", "0".repeat(68)),
+            format!("01{}", "1".repeat(68)),
+        ];
+        assert_eq!(
+            solve_puzzle1(wide_nums.into_iter()),
+            // 3 * 2^68 * (2^68 - 1)
+            "261336857795280739938986254792059927920640"
+        );
+    }
+
+    #[test]
+    fn test_filter_trace() {
+        let nums = parse_input(EXAMPLE_INPUT.into_iter().map(|s| s.to_owned()));
+        let trace = bit_criteria_filter(&nums, BitCriterion::OXYGEN_GENERATOR);
+        let remaining_counts: Vec<usize> = trace
+            .steps
+            .iter()
+            .map(|step| step.remaining.len())
+            .collect();
+        assert_eq!(remaining_counts, [7, 4, 3, 2, 1]);
+        assert_eq!(trace.selected, Some(3));
+
+        let trace = bit_criteria_filter(&nums, BitCriterion::CO2_SCRUBBER);
+        assert_eq!(trace.steps.len(), 3);
+        assert_eq!(EXAMPLE_INPUT[trace.selected.unwrap()], "01010");
+
+        let trace = bit_criteria_filter(&vec![nums[3].clone(); 2], BitCriterion::CO2_SCRUBBER);
+        assert_eq!(trace.selected, None);
+    }
 }
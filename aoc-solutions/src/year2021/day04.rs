@@ -1,46 +1,108 @@
-struct BingoBoard {
-    numbers: [u8; 5 * 5],
-    marked: [bool; 5 * 5],
+use ndarray::Array2;
+
+/// The ways in which a board can win besides completing a row or column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BingoRules {
+    /// Whether completing one of the two diagonals of a square board also wins.
+    pub diagonals: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BingoBoard {
+    numbers: Array2<u64>,
+    marked: Array2<bool>,
 }
 
 impl BingoBoard {
-    pub fn has_won(&self) -> bool {
-        let row_won = self
-            .marked
-            .chunks(5)
-            .any(|row| row.iter().all(|marked| *marked));
-
-        if row_won {
-            return true;
+    pub fn new(numbers: Array2<u64>) -> Self {
+        Self {
+            marked: Array2::from_elem(numbers.raw_dim(), false),
+            numbers,
         }
+    }
 
-        let column_won = (0..5).any(|i| self.marked[i..].iter().step_by(5).all(|marked| *marked));
+    /// Whether the row, column or (if enabled and on one) diagonal through the cell at `index` is
+    /// fully marked.
+    fn completes_line(&self, (row, column): (usize, usize), rules: BingoRules) -> bool {
+        let (rows, columns) = self.marked.dim();
+        let is_square = rows == columns;
+
+        self.marked.row(row).iter().all(|&marked| marked)
+            || self.marked.column(column).iter().all(|&marked| marked)
+            || (rules.diagonals
+                && is_square
+                && ((row == column && self.marked.diag().iter().all(|&marked| marked))
+                    || (row + column == columns - 1
+                        && (0..rows).all(|i| self.marked[(i, columns - 1 - i)]))))
+    }
 
-        column_won
+    /// Marks all cells with the called number and returns whether that completed a line.
+    pub fn mark(&mut self, called_num: u64, rules: BingoRules) -> bool {
+        let mut marked_indices = Vec::new();
+        for (index, &num) in self.numbers.indexed_iter() {
+            if num == called_num {
+                self.marked[index] = true;
+                marked_indices.push(index);
+            }
+        }
+
+        marked_indices
+            .into_iter()
+            .any(|index| self.completes_line(index, rules))
     }
 
-    pub fn score(&self, last_called_num: u8) -> usize {
+    pub fn score(&self, last_called_num: u64) -> u64 {
         self.numbers
             .iter()
-            .copied()
-            .zip(self.marked)
-            .filter_map(|(num, marked)| (!marked).then_some(num))
-            .map(|unmarked_num| unmarked_num as usize)
-            .sum::<usize>()
-            * last_called_num as usize
+            .zip(&self.marked)
+            .filter_map(|(&num, &marked)| (!marked).then_some(num))
+            .sum::<u64>()
+            * last_called_num
     }
+}
 
-    pub fn mark_if_present(&mut self, called_num: u8) {
-        self.numbers
-            .iter()
-            .copied()
-            .zip(self.marked.as_mut())
-            .filter(|(num, _marked)| *num == called_num)
-            .for_each(|(_num, marked)| *marked = true)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Win {
+    /// The index of the board in the order of the input.
+    pub board_index: usize,
+    /// The index of the winning number among the called numbers.
+    pub turn: usize,
+    pub called_num: u64,
+    pub score: u64,
+}
+
+/// Calls all numbers and returns the wins of the boards in order, boards winning on the same
+/// turn ordered by index.
+///
+/// Each board only wins once and is not played anymore afterwards. Boards that never win are
+/// missing from the result.
+pub fn play(called_nums: &[u64], mut boards: Vec<BingoBoard>, rules: BingoRules) -> Vec<Win> {
+    let mut wins = Vec::with_capacity(boards.len());
+    let mut has_won = vec![false; boards.len()];
+
+    for (turn, &called_num) in called_nums.iter().enumerate() {
+        for (board_index, board) in boards.iter_mut().enumerate() {
+            if !has_won[board_index] && board.mark(called_num, rules) {
+                has_won[board_index] = true;
+                wins.push(Win {
+                    board_index,
+                    turn,
+                    called_num,
+                    score: board.score(called_num),
+                });
+            }
+        }
+        if wins.len() == boards.len() {
+            break;
+        }
     }
+
+    wins
 }
 
-fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> (Vec<u8>, Vec<BingoBoard>) {
+/// Parses the called numbers and the boards, which are separated by empty lines and may be of
+/// any size.
+fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> (Vec<u64>, Vec<BingoBoard>) {
     let called_nums = input_lines
         .next()
         .expect("expected line with numbers, found EOF")
@@ -51,80 +113,105 @@ fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> (Vec<u8>, Vec<
     let mut bingo_boards = vec![];
 
     let mut board_nums = vec![];
-
-    loop {
-        // Drop empty line between boards or stop if at end of file
-        if input_lines.next().is_none() {
-            break;
-        }
-
-        for _ in 0..5 {
-            board_nums.extend(
-                input_lines
-                    .next()
-                    .expect("expected board line, found EOF")
-                    .split_whitespace()
-                    .map(|num| {
-                        num.parse::<u8>()
-                            .expect("non-number where a bingo board was expected")
-                    }),
-            );
+    let mut num_columns = None;
+
+    for line in input_lines.map(Some).chain([None]) {
+        match line.filter(|line| !line.trim().is_empty()) {
+            Some(line) => {
+                let row_len = board_nums.len();
+                board_nums.extend(line.split_whitespace().map(|num| {
+                    num.parse::<u64>()
+                        .expect("non-number where a bingo board was expected")
+                }));
+                let row_len = board_nums.len() - row_len;
+                if *num_columns.get_or_insert(row_len) != row_len {
+                    panic!("expected all rows of a board to be of the same length");
+                }
+            }
+            // An empty line or the end of the file finishes the current board, if any.
+            None => {
+                if let Some(num_columns) = num_columns.take() {
+                    let numbers = Array2::from_shape_vec(
+                        (board_nums.len() / num_columns, num_columns),
+                        std::mem::take(&mut board_nums),
+                    )
+                    .unwrap();
+                    bingo_boards.push(BingoBoard::new(numbers));
+                }
+            }
         }
-
-        let board_nums_array: [u8; 5 * 5] = board_nums
-            .as_slice()
-            .try_into()
-            .expect("incorrect number of numbers per board, expected 5 * 5 = 25");
-
-        bingo_boards.push(BingoBoard {
-            numbers: board_nums_array,
-            marked: [false; 5 * 5],
-        });
-
-        board_nums.clear();
     }
 
     (called_nums, bingo_boards)
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let (called_nums, mut bingo_boards) = parse_input(input_lines);
-
-    let mut solution = None;
-
-    for called_num in called_nums {
-        for board in bingo_boards.iter_mut() {
-            board.mark_if_present(called_num);
-        }
+    let (called_nums, bingo_boards) = parse_input(input_lines);
 
-        if let Some(board) = bingo_boards.iter().find(|board| board.has_won()) {
-            solution = Some(board.score(called_num));
-            break;
-        }
-    }
+    let wins = play(&called_nums, bingo_boards, BingoRules::default());
 
-    solution.unwrap().to_string()
+    wins.first()
+        .expect("expected at least one board to win")
+        .score
+        .to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let (called_nums, bingo_boards) = parse_input(input_lines);
 
-    let (_num_calls_to_win, score) = bingo_boards
-        .into_iter()
-        .filter_map(|mut board| {
-            let mut win = None;
-            for (i, called_num) in called_nums.iter().copied().enumerate() {
-                board.mark_if_present(called_num);
+    let wins = play(&called_nums, bingo_boards, BingoRules::default());
 
-                if board.has_won() {
-                    win = Some((i, board.score(called_num)));
-                    break;
-                }
-            }
-            win
-        })
-        .max_by_key(|(num_calls_to_win, _score)| *num_calls_to_win)
-        .unwrap();
+    wins.last()
+        .expect("expected at least one board to win")
+        .score
+        .to_string()
+}
 
-    score.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        5,1000,9,4,1,8
+
+        1000 2 3
+        4    5 6
+
+        1 2 3
+        4 5 6
+        7 8 9
+    "};
+
+    #[test]
+    fn test_play() {
+        let (called_nums, boards) = parse_input(INPUT.lines().map(String::from));
+        assert_eq!(boards[0].numbers.dim(), (2, 3));
+        assert_eq!(boards[1].numbers.dim(), (3, 3));
+
+        let first_win = Win {
+            board_index: 0,
+            turn: 3,
+            called_num: 4,
+            score: (2 + 3 + 6) * 4,
+        };
+        assert_eq!(
+            play(&called_nums, boards.clone(), BingoRules::default()),
+            vec![first_win]
+        );
+        assert_eq!(
+            play(&called_nums, boards, BingoRules { diagonals: true }),
+            vec![
+                first_win,
+                Win {
+                    board_index: 1,
+                    turn: 4,
+                    called_num: 1,
+                    // The sum of the unmarked numbers, times the called number 1.
+                    score: 2 + 3 + 6 + 7 + 8,
+                }
+            ]
+        );
+    }
 }
@@ -1,8 +1,54 @@
-use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
 
-struct Line {
-    start: [u16; 2],
-    end: [u16; 2],
+/// The directions of the lines vents can form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Horizontal,
+    Vertical,
+    /// Both coordinates increase together.
+    Diagonal,
+    /// One coordinate increases as the other decreases.
+    AntiDiagonal,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Horizontal,
+        Direction::Vertical,
+        Direction::Diagonal,
+        Direction::AntiDiagonal,
+    ];
+
+    /// The coefficients `[a, b]` with which all points of a line in this direction satisfy
+    /// `a * x + b * y == offset` for some offset.
+    const fn coefficients(self) -> [i64; 2] {
+        match self {
+            Direction::Horizontal => [0, 1],
+            Direction::Vertical => [1, 0],
+            Direction::Diagonal => [-1, 1],
+            Direction::AntiDiagonal => [1, 1],
+        }
+    }
+
+    fn offset(self, [x, y]: [i64; 2]) -> i64 {
+        let [a, b] = self.coefficients();
+        a * x + b * y
+    }
+
+    /// The coordinate that tells the points of a line in this direction apart.
+    const fn position(self, [x, y]: [i64; 2]) -> i64 {
+        match self {
+            Direction::Vertical => y,
+            _ => x,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub start: [i64; 2],
+    pub end: [i64; 2],
 }
 
 impl Line {
@@ -10,18 +56,28 @@ impl Line {
         self.start[0] == self.end[0] || self.start[1] == self.end[1]
     }
 
-    fn max_coord_value(&self, i: usize) -> u16 {
-        cmp::max(self.start[i], self.end[i])
-    }
-
-    pub fn max_coords(&self) -> [u16; 2] {
-        [self.max_coord_value(0), self.max_coord_value(1)]
+    /// The direction of the line, `None` if it is neither axis-aligned nor at 45 degrees.
+    ///
+    /// Single points count as horizontal lines.
+    pub fn direction(&self) -> Option<Direction> {
+        let [dx, dy] = [self.end[0] - self.start[0], self.end[1] - self.start[1]];
+        if dy == 0 {
+            Some(Direction::Horizontal)
+        } else if dx == 0 {
+            Some(Direction::Vertical)
+        } else if dx == dy {
+            Some(Direction::Diagonal)
+        } else if dx == -dy {
+            Some(Direction::AntiDiagonal)
+        } else {
+            None
+        }
     }
 
-    pub fn step(&self) -> [i8; 2] {
+    pub fn step(&self) -> [i64; 2] {
         [
-            (self.end[0] as i32 - self.start[0] as i32).signum() as i8,
-            (self.end[1] as i32 - self.start[1] as i32).signum() as i8,
+            (self.end[0] - self.start[0]).signum(),
+            (self.end[1] - self.start[1]).signum(),
         ]
     }
 }
@@ -33,41 +89,37 @@ impl IntoIterator for &Line {
 
     fn into_iter(self) -> Self::IntoIter {
         LineIterator {
-            current_pos: [i32::from(self.start[0]), i32::from(self.start[1])],
+            current_pos: self.start,
             step: self.step(),
-            end: [i32::from(self.end[0]), i32::from(self.end[1])],
+            remaining_points: self.end[0]
+                .abs_diff(self.start[0])
+                .max(self.end[1].abs_diff(self.start[1]))
+                + 1,
         }
     }
 }
 
-struct LineIterator {
-    current_pos: [i32; 2],
-    step: [i8; 2],
-    end: [i32; 2],
+pub struct LineIterator {
+    current_pos: [i64; 2],
+    step: [i64; 2],
+    remaining_points: u64,
 }
 
 impl Iterator for LineIterator {
-    type Item = [u16; 2];
+    type Item = [i64; 2];
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Check if current position is after `end` in the `step` direction.
-        if self.step[0] >= 0 && self.current_pos[0] > self.end[0]
-            || self.step[0] < 0 && self.current_pos[0] < self.end[0]
-            || self.step[1] >= 0 && self.current_pos[1] > self.end[1]
-            || self.step[1] < 0 && self.current_pos[1] < self.end[1]
-        {
+        if self.remaining_points == 0 {
             return None;
         }
+        self.remaining_points -= 1;
 
         let current_pos = self.current_pos;
 
-        self.current_pos[0] += self.step[0] as i32;
-        self.current_pos[1] += self.step[1] as i32;
+        self.current_pos[0] += self.step[0];
+        self.current_pos[1] += self.step[1];
 
-        Some([
-            u16::try_from(current_pos[0]).expect("expected non-negative coordinates only"),
-            u16::try_from(current_pos[1]).expect("expected non-negative coordinates only"),
-        ])
+        Some(current_pos)
     }
 }
 
@@ -86,41 +138,190 @@ fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<Line> {
 
             Line {
                 start: [
-                    start_x_str.parse().expect("expected unsigned int x value"),
-                    start_y_str.parse().expect("expected unsigned int y value"),
+                    start_x_str.parse().expect("expected integer x value"),
+                    start_y_str.parse().expect("expected integer y value"),
                 ],
                 end: [
-                    end_x_str.parse().expect("expected unsigned int x value"),
-                    end_y_str.parse().expect("expected unsigned int y value"),
+                    end_x_str.parse().expect("expected integer x value"),
+                    end_y_str.parse().expect("expected integer y value"),
                 ],
             }
         })
         .collect()
 }
 
-fn max_coords_of_lines<'a, I: Iterator<Item = &'a Line>>(lines: I) -> [u16; 2] {
-    lines.map(Line::max_coords).fold(
-        [0; 2],
-        |[global_max_x, global_max_y], [line_max_x, line_max_y]| {
-            [
-                cmp::max(global_max_x, line_max_x),
-                cmp::max(global_max_y, line_max_y),
-            ]
-        },
-    )
+/// The vents on one infinite line of the ocean floor, as sorted, disjoint and non-adjacent ranges
+/// of positions along it.
+#[derive(Debug, Default)]
+struct Carrier {
+    /// The positions covered by at least one vent line.
+    covered: Vec<RangeInclusive<i64>>,
+    /// The positions covered by at least two vent lines.
+    overlapping: Vec<RangeInclusive<i64>>,
+}
+
+fn ranges_contain(ranges: &[RangeInclusive<i64>], position: i64) -> bool {
+    let index = ranges.partition_point(|range| *range.end() < position);
+    ranges
+        .get(index)
+        .into_iter()
+        .any(|range| range.contains(&position))
+}
+
+fn range_len(range: &RangeInclusive<i64>) -> u64 {
+    range.end().abs_diff(*range.start()) + 1
+}
+
+impl Carrier {
+    /// Sweeps over the start and end positions of the vent lines on the carrier, keeping track of
+    /// how many of them cover the positions in between.
+    fn from_ranges(ranges: &[RangeInclusive<i64>]) -> Self {
+        let mut events: Vec<(i64, i32)> = ranges
+            .iter()
+            .flat_map(|range| [(*range.start(), 1), (range.end() + 1, -1)])
+            .collect();
+        events.sort_unstable();
+
+        let mut carrier = Self::default();
+        let mut coverage = 0;
+        let mut covered_start = 0;
+        let mut overlapping_start = 0;
+        let mut index = 0;
+        while index < events.len() {
+            let position = events[index].0;
+            let previous_coverage = coverage;
+            while index < events.len() && events[index].0 == position {
+                coverage += events[index].1;
+                index += 1;
+            }
+
+            for (threshold, start, ranges) in [
+                (1, &mut covered_start, &mut carrier.covered),
+                (2, &mut overlapping_start, &mut carrier.overlapping),
+            ] {
+                if previous_coverage < threshold && coverage >= threshold {
+                    *start = position;
+                } else if previous_coverage >= threshold && coverage < threshold {
+                    ranges.push(*start..=position - 1);
+                }
+            }
+        }
+
+        carrier
+    }
+}
+
+/// The point shared by the lines in different directions with the given offsets, if it has
+/// integer coordinates.
+fn crossing(
+    (direction_a, offset_a): (Direction, i64),
+    (direction_b, offset_b): (Direction, i64),
+) -> Option<[i64; 2]> {
+    let [a1, b1] = direction_a.coefficients();
+    let [a2, b2] = direction_b.coefficients();
+    let determinant = a1 * b2 - a2 * b1;
+    let x = offset_a * b2 - offset_b * b1;
+    let y = a1 * offset_b - a2 * offset_a;
+
+    (x % determinant == 0 && y % determinant == 0).then(|| [x / determinant, y / determinant])
+}
+
+/// Counts the points covered by at least two vent lines without visiting the points one by one,
+/// so that the coordinates may be arbitrarily large.
+///
+/// The lines are grouped by the infinite line they lie on, called their carrier, on which their
+/// overlaps follow from a sweep over their ends. Points of two different carriers are the points
+/// where the carriers cross, which are found by intersecting each pair of covered ranges. Each
+/// crossing is counted once, while the overlaps of the carriers are added up without the
+/// crossings on them.
+///
+/// Fails if a line is neither axis-aligned nor at 45 degrees.
+pub fn count_overlapping_points(lines: &[Line]) -> Result<u64, &'static str> {
+    let mut ranges_by_carrier: HashMap<(Direction, i64), Vec<RangeInclusive<i64>>> = HashMap::new();
+    for line in lines {
+        let direction = line
+            .direction()
+            .ok_or("expected horizontal, vertical or diagonal lines only")?;
+        let [start, end] = [line.start, line.end].map(|point| direction.position(point));
+        ranges_by_carrier
+            .entry((direction, direction.offset(line.start)))
+            .or_default()
+            .push(start.min(end)..=start.max(end));
+    }
+
+    let carriers: HashMap<(Direction, i64), Carrier> = ranges_by_carrier
+        .into_iter()
+        .map(|(key, ranges)| (key, Carrier::from_ranges(&ranges)))
+        .collect();
+
+    let covered_ranges: Vec<((Direction, i64), &RangeInclusive<i64>)> = carriers
+        .iter()
+        .flat_map(|(&key, carrier)| carrier.covered.iter().map(move |range| (key, range)))
+        .collect();
+    let mut crossings = HashSet::new();
+    for (index, &(key_a, range_a)) in covered_ranges.iter().enumerate() {
+        for &(key_b, range_b) in &covered_ranges[index + 1..] {
+            if key_a.0 == key_b.0 {
+                continue;
+            }
+            if let Some(point) = crossing(key_a, key_b) {
+                if range_a.contains(&key_a.0.position(point))
+                    && range_b.contains(&key_b.0.position(point))
+                {
+                    crossings.insert(point);
+                }
+            }
+        }
+    }
+
+    let overlapping_on_carriers: u64 = carriers
+        .values()
+        .flat_map(|carrier| &carrier.overlapping)
+        .map(range_len)
+        .sum();
+    let crossings_on_overlaps = crossings
+        .iter()
+        .map(|&point| {
+            Direction::ALL
+                .iter()
+                .filter(|&&direction| {
+                    carriers
+                        .get(&(direction, direction.offset(point)))
+                        .into_iter()
+                        .any(|carrier| {
+                            ranges_contain(&carrier.overlapping, direction.position(point))
+                        })
+                })
+                .count() as u64
+        })
+        .sum::<u64>();
+
+    Ok(overlapping_on_carriers - crossings_on_overlaps + crossings.len() as u64)
 }
 
-fn count_overlapping_points(lines: Vec<Line>) -> usize {
-    let [max_x, max_y] = max_coords_of_lines(lines.iter());
+/// Counts the points covered by at least two vent lines by drawing all lines onto a grid spanning
+/// their bounding box, which is only feasible for small coordinates.
+pub fn count_overlapping_points_dense(lines: &[Line]) -> usize {
+    if lines.is_empty() {
+        return 0;
+    }
+
+    let corners = lines.iter().flat_map(|line| [line.start, line.end]);
+    let (min, max) = corners.fold(([i64::MAX; 2], [i64::MIN; 2]), |(min, max), [x, y]| {
+        (
+            [min[0].min(x), min[1].min(y)],
+            [max[0].max(x), max[1].max(y)],
+        )
+    });
 
-    let ocean_floor_width = (max_x + 1) as usize;
-    let ocean_floor_height = (max_y + 1) as usize;
+    let ocean_floor_width = (max[0] - min[0] + 1) as usize;
+    let ocean_floor_height = (max[1] - min[1] + 1) as usize;
 
-    let mut ocean_floor = vec![0u8; ocean_floor_width * ocean_floor_height];
+    let mut ocean_floor = vec![0u16; ocean_floor_width * ocean_floor_height];
 
-    for line in &lines {
+    for line in lines {
         for [x, y] in line {
-            ocean_floor[x as usize + y as usize * ocean_floor_width] += 1;
+            ocean_floor[(x - min[0]) as usize + (y - min[1]) as usize * ocean_floor_width] += 1;
         }
     }
 
@@ -132,7 +333,7 @@ pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
 
     vent_lines.retain(Line::is_axis_aligned);
 
-    let solution = count_overlapping_points(vent_lines);
+    let solution = count_overlapping_points(&vent_lines).unwrap();
 
     solution.to_string()
 }
@@ -140,7 +341,88 @@ pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let vent_lines = parse_input(input_lines);
 
-    let solution = count_overlapping_points(vent_lines);
+    let solution = count_overlapping_points(&vent_lines).unwrap();
 
     solution.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const EXAMPLE: &str = indoc! {"
+        0,9 -> 5,9
+        8,0 -> 0,8
+        9,4 -> 3,4
+        2,2 -> 2,1
+        7,0 -> 7,4
+        6,4 -> 2,0
+        0,9 -> 2,9
+        3,4 -> 1,4
+        0,0 -> 8,8
+        5,5 -> 8,2
+    "};
+
+    #[test]
+    fn test_example_and_dense_fallback() {
+        let mut lines = parse_input(EXAMPLE.lines().map(String::from));
+        assert_eq!(count_overlapping_points(&lines), Ok(12));
+        assert_eq!(count_overlapping_points_dense(&lines), 12);
+
+        lines.retain(Line::is_axis_aligned);
+        assert_eq!(count_overlapping_points(&lines), Ok(5));
+        assert_eq!(count_overlapping_points_dense(&lines), 5);
+
+        // Collinear overlaps, crossings at overlaps and negative coordinates.
+        let mut lines = parse_input(EXAMPLE.lines().map(String::from));
+        lines.extend(parse_input(
+            [
+                "-3,-3 -> 4,4",
+                "2,2 -> 2,-5",
+                "2,-2 -> 2,-4",
+                "-4,2 -> 3,-5",
+                "1,-3 -> 1,-3",
+                "-5,-3 -> 6,-3",
+                "6,-3 -> -1,-3",
+            ]
+            .into_iter()
+            .map(String::from),
+        ));
+        assert_eq!(
+            count_overlapping_points(&lines),
+            Ok(count_overlapping_points_dense(&lines) as u64)
+        );
+    }
+
+    #[test]
+    fn test_large_coordinates() {
+        let lines = [
+            Line {
+                start: [-1_000_000_000_000, 7],
+                end: [1_000_000_000_000, 7],
+            },
+            Line {
+                start: [0, 7],
+                end: [3_000_000_000_000, 7],
+            },
+            Line {
+                start: [5, -1_000_000_000_000],
+                end: [5, 1_000_000_000_000],
+            },
+            Line {
+                start: [-10, -10],
+                end: [10, 10],
+            },
+        ];
+        // The collinear overlap plus the crossing of the vertical and the diagonal line.
+        assert_eq!(count_overlapping_points(&lines), Ok(1_000_000_000_001 + 1));
+
+        assert!(count_overlapping_points(&[Line {
+            start: [0, 0],
+            end: [1, 2],
+        }])
+        .is_err());
+    }
+}
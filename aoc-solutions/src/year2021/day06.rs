@@ -1,3 +1,5 @@
+use crate::linalg::{Arithmetic, Element, SquareMatrix};
+
 fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> [u64; 9] {
     let initial_population_line = input_lines
         .next()
@@ -16,47 +18,94 @@ fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> [u64; 9] {
         })
 }
 
-fn simulate_lanternfish_population(initial_population: [u64; 9], num_days: usize) -> [u64; 9] {
-    let mut current_population = initial_population;
-
-    for _ in 0..num_days {
-        let mut new_population = current_population;
-
-        for age in (1..=8).rev() {
-            new_population[age - 1] = current_population[age]
+/// The matrix mapping the number of lanternfish per timer value to those of the next day.
+fn lanternfish_day_matrix<T: Element>() -> SquareMatrix<T> {
+    SquareMatrix::from_fn(9, |new_timer, timer| {
+        if timer == new_timer + 1 || (timer == 0 && (new_timer == 6 || new_timer == 8)) {
+            T::ONE
+        } else {
+            T::ZERO
         }
+    })
+}
 
-        new_population[8] = current_population[0];
-        new_population[6] += current_population[0];
+fn simulate_lanternfish_population<T: Element + From<u64>>(
+    initial_population: [u64; 9],
+    num_days: u64,
+    arithmetic: Arithmetic<T>,
+) -> Option<Vec<T>> {
+    let initial_population: Vec<T> = initial_population.iter().map(|&n| T::from(n)).collect();
 
-        current_population = new_population;
-    }
-
-    current_population
+    lanternfish_day_matrix()
+        .pow(num_days, arithmetic)?
+        .mul_vector(&initial_population, arithmetic)
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+/// Counts the lanternfish after `num_days` days, in logarithmic time in `num_days`.
+///
+/// Returns `None` if the count overflows with [`Arithmetic::Checked`].
+pub fn count_lanternfish<T, I>(
+    input_lines: I,
+    num_days: u64,
+    arithmetic: Arithmetic<T>,
+) -> Option<T>
+where
+    T: Element + From<u64>,
+    I: Iterator<Item = String>,
+{
     let initial_population = parse_input(input_lines);
 
-    let final_population = simulate_lanternfish_population(initial_population, 80);
+    simulate_lanternfish_population(initial_population, num_days, arithmetic)?
+        .into_iter()
+        .try_fold(T::ZERO, |total, num| arithmetic.add(total, num))
+}
 
-    final_population
-        .iter()
-        .copied()
-        .map(|num| num as u64)
-        .sum::<u64>()
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    count_lanternfish::<u64, _>(input_lines, 80, Arithmetic::Checked)
+        .expect("lanternfish count overflowed")
         .to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let initial_population = parse_input(input_lines);
+    count_lanternfish::<u64, _>(input_lines, 256, Arithmetic::Checked)
+        .expect("lanternfish count overflowed")
+        .to_string()
+}
 
-    let final_population = simulate_lanternfish_population(initial_population, 256);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    final_population
-        .iter()
-        .copied()
-        .map(|num| num as u64)
-        .sum::<u64>()
-        .to_string()
+    fn example_input() -> impl Iterator<Item = String> {
+        std::iter::once(String::from("3,4,3,1,2"))
+    }
+
+    #[test]
+    fn test_example_counts() {
+        assert_eq!(
+            count_lanternfish::<u64, _>(example_input(), 80, Arithmetic::Checked),
+            Some(5934)
+        );
+        assert_eq!(
+            count_lanternfish::<u64, _>(example_input(), 256, Arithmetic::Checked),
+            Some(26984457539)
+        );
+    }
+
+    #[test]
+    fn test_huge_number_of_days() {
+        const NUM_DAYS: u64 = 1_000_000_000_000_000_000;
+        assert_eq!(
+            count_lanternfish::<u128, _>(example_input(), NUM_DAYS, Arithmetic::Checked),
+            None
+        );
+        assert_eq!(
+            count_lanternfish::<u64, _>(
+                example_input(),
+                NUM_DAYS,
+                Arithmetic::Modular(1_000_000_007)
+            ),
+            Some(860170227)
+        );
+    }
 }
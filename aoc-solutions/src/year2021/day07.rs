@@ -1,4 +1,4 @@
-fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> Vec<u16> {
+fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> Vec<u64> {
     let crab_positions_line = input_lines
         .next()
         .expect("expected one line of comma-separated horizontal positions for the crabs");
@@ -7,56 +7,202 @@ fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> Vec<u16> {
         .split(',')
         .map(|horizontal_position_str| {
             horizontal_position_str
-                .parse::<u16>()
+                .parse::<u64>()
                 .expect("expected comma separated unsigned integers")
         })
         .collect()
 }
 
-/// Computes the median, assuming that it is an integral number.
-fn integer_median(nums: &mut Vec<u16>) -> u16 {
-    let len = nums.len();
-    if len % 2 != 0 {
-        *nums.select_nth_unstable(len / 2).1
-    } else {
-        (*nums.select_nth_unstable(len / 2 - 1).1 + *nums.select_nth_unstable(len / 2).1) / 2
+/// The fuel a crab needs to move a given distance.
+///
+/// The cost must be convex in the distance and never decrease with it, so that the total cost of
+/// aligning all crabs is convex in the alignment position.
+pub trait FuelCost {
+    fn cost(&self, distance: u64) -> u64;
+}
+
+impl<F: Fn(u64) -> u64> FuelCost for F {
+    fn cost(&self, distance: u64) -> u64 {
+        self(distance)
     }
 }
 
-fn triangular_num(n: u32) -> u32 {
-    n * (n + 1) / 2
+/// Every step costs one unit of fuel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn cost(&self, distance: u64) -> u64 {
+        distance
+    }
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let mut horizontal_crab_positions = parse_input(input_lines);
+/// Every step costs one unit of fuel more than the previous one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn cost(&self, distance: u64) -> u64 {
+        distance * (distance + 1) / 2
+    }
+}
 
-    // The median minimizes the sum absolute deviations.
-    let optimal_target_pos = integer_median(&mut horizontal_crab_positions);
+/// The fuel grows with the square of the distance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quadratic;
 
-    let optimal_fuel_cost = horizontal_crab_positions
+impl FuelCost for Quadratic {
+    fn cost(&self, distance: u64) -> u64 {
+        distance * distance
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: u64,
+    pub fuel_cost: u64,
+}
+
+fn total_fuel_cost<C: FuelCost>(crab_positions: &[u64], fuel_cost: &C, position: u64) -> u64 {
+    crab_positions
         .iter()
-        .map(|pos| pos.abs_diff(optimal_target_pos) as u32)
-        .sum::<u32>();
+        .map(|&crab_position| fuel_cost.cost(crab_position.abs_diff(position)))
+        .sum()
+}
+
+/// Finds the position that minimizes the total fuel cost of aligning all crabs there, or `None` if
+/// there are no crabs.
+///
+/// As the total cost is convex, the optimum is the first position from which moving one further
+/// to the right does not decrease the cost. It is searched for starting at the median, which is
+/// optimal for linear costs, by doubling the step size until the optimum is bracketed and then
+/// bisecting. Of several optimal positions, the leftmost is returned.
+pub fn align_crabs<C: FuelCost>(crab_positions: &[u64], fuel_cost: C) -> Option<Alignment> {
+    let mut sorted_positions = crab_positions.to_vec();
+    sorted_positions.sort_unstable();
+    let (&min, &max) = (sorted_positions.first()?, sorted_positions.last()?);
+    let median = sorted_positions[(sorted_positions.len() - 1) / 2];
+
+    let total = |position| total_fuel_cost(&sorted_positions, &fuel_cost, position);
+    let is_past_optimum = |position| position == max || total(position + 1) >= total(position);
 
-    optimal_fuel_cost.to_string()
+    // The optimum lies within `low..=high`, and `high` is always past it.
+    let (mut low, mut high) = if is_past_optimum(median) {
+        let mut step = 1;
+        let mut high = median;
+        loop {
+            let low = high.saturating_sub(step).max(min);
+            if low == min || !is_past_optimum(low) {
+                break (low, high);
+            }
+            high = low;
+            step *= 2;
+        }
+    } else {
+        let mut step = 1;
+        let mut low = median + 1;
+        loop {
+            let high = (low + step).min(max);
+            if is_past_optimum(high) {
+                break (low, high);
+            }
+            low = high + 1;
+            step *= 2;
+        }
+    };
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if is_past_optimum(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+
+    Some(Alignment {
+        position: low,
+        fuel_cost: total(low),
+    })
+}
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let horizontal_crab_positions = parse_input(input_lines);
+
+    align_crabs(&horizontal_crab_positions, Linear)
+        .expect("expected at least one crab")
+        .fuel_cost
+        .to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let horizontal_crab_positions = parse_input(input_lines);
 
-    // The mean minimizes the sum of squared deviations, and apparently also
-    // the sum of the triangular numbers of the deviations?
-    // (which is what this puzzle requires).
-    let optimal_target_pos = (horizontal_crab_positions
-        .iter()
-        .map(|&pos| pos as u32)
-        .sum::<u32>()
-        / horizontal_crab_positions.len() as u32) as u16;
+    align_crabs(&horizontal_crab_positions, Triangular)
+        .expect("expected at least one crab")
+        .fuel_cost
+        .to_string()
+}
 
-    let optimal_fuel_cost = horizontal_crab_positions
-        .iter()
-        .map(|pos| triangular_num(pos.abs_diff(optimal_target_pos) as u32))
-        .sum::<u32>();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_POSITIONS: [u64; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+    fn brute_force<C: FuelCost>(crab_positions: &[u64], fuel_cost: C) -> Alignment {
+        let max = *crab_positions.iter().max().unwrap();
+        (0..=max)
+            .map(|position| Alignment {
+                position,
+                fuel_cost: total_fuel_cost(crab_positions, &fuel_cost, position),
+            })
+            .min_by_key(|alignment| alignment.fuel_cost)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(
+            align_crabs(&EXAMPLE_POSITIONS, Linear),
+            Some(Alignment {
+                position: 2,
+                fuel_cost: 37
+            })
+        );
+        assert_eq!(
+            align_crabs(&EXAMPLE_POSITIONS, Triangular),
+            Some(Alignment {
+                position: 5,
+                fuel_cost: 168
+            })
+        );
+    }
 
-    optimal_fuel_cost.to_string()
+    #[test]
+    fn test_other_costs_and_inputs() {
+        let inputs: [&[u64]; 5] = [
+            // The truncated mean 0 is not optimal for triangular costs.
+            &[0, 1, 1],
+            &[7],
+            &[3, 3, 3, 3],
+            &[0, 1000, 1000, 1000, 1000, 1000, 1000],
+            &EXAMPLE_POSITIONS,
+        ];
+        for crab_positions in inputs {
+            assert_eq!(
+                align_crabs(crab_positions, Triangular),
+                Some(brute_force(crab_positions, Triangular))
+            );
+            assert_eq!(
+                align_crabs(crab_positions, Quadratic),
+                Some(brute_force(crab_positions, Quadratic))
+            );
+            let cubic = |distance: u64| distance.pow(3);
+            assert_eq!(
+                align_crabs(crab_positions, cubic),
+                Some(brute_force(crab_positions, cubic))
+            );
+        }
+        assert_eq!(align_crabs(&[], Linear), None);
+    }
 }
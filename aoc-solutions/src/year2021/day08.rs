@@ -1,62 +1,250 @@
 use std::collections::HashMap;
+use std::fmt::{self, Display};
 
-use itertools::Itertools;
+use crate::letters::LetterSet;
 
-fn parse_input<I: Iterator<Item = String>>(
-    input_lines: I,
-) -> Vec<(Vec<[bool; 7]>, [[bool; 7]; 4])> {
-    input_lines
-        .map(|line| {
-            let (signal_patterns_str, output_value_patterns_str) = line
-                .split_once(" | ")
-                .expect("expected separator between signal patterns and output values");
+/// The segments of a display lit for a digit, named by the letters starting at `a`.
+pub type Pattern = LetterSet;
 
-            let signal_patterns = signal_patterns_str
-                .split(' ')
-                .map(|pattern_str| {
-                    let mut pattern = [false; 7];
-                    for b in pattern_str.bytes() {
-                        pattern[(b - b'a') as usize] = true;
-                    }
-                    pattern
-                })
+pub fn parse_pattern(pattern_str: &str) -> Pattern {
+    pattern_str
+        .parse()
+        .expect("Segments must be the letters a to z")
+}
+
+/// Describes a pattern among others in a way that does not depend on how the segments are wired:
+/// by its number of segments and the sorted numbers of segments it shares with each pattern.
+type Signature = (usize, Vec<usize>);
+
+fn signatures(patterns: &[Pattern]) -> Vec<Signature> {
+    patterns
+        .iter()
+        .map(|&pattern| {
+            let mut overlaps: Vec<usize> = patterns
+                .iter()
+                .map(|&other| (pattern & other).len())
                 .collect();
+            overlaps.sort_unstable();
+            (pattern.len(), overlaps)
+        })
+        .collect()
+}
+
+/// For each of the first `num_segments` segments, the set of digits using it as a bit set, sorted.
+fn sorted_segment_memberships(num_segments: usize, digit_patterns: &[Pattern]) -> Vec<u64> {
+    let mut memberships: Vec<u64> = ('a'..='z')
+        .take(num_segments)
+        .map(|segment| {
+            digit_patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, &pattern)| pattern.contains(segment))
+                .fold(0, |membership, (digit, _)| membership | 1 << digit)
+        })
+        .collect();
+    memberships.sort_unstable();
+    memberships
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The digits with the given values cannot be told apart by their numbers of segments and
+    /// their overlaps with the other digits.
+    AmbiguousDigits(usize, usize),
+    /// The entry has a different number of signal patterns than the display has digits.
+    WrongPatternCount { expected: usize, found: usize },
+    /// The signal pattern does not fit any digit of the display.
+    UnmatchedPattern(String),
+    /// More than one signal pattern fits the digit with the given value.
+    DuplicateDigit(usize),
+    /// Each signal pattern fits a digit, but no rewiring of the segments produces all of them.
+    InconsistentWiring,
+    /// The output pattern is not among the signal patterns.
+    UnknownOutputPattern(String),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::AmbiguousDigits(a, b) => {
+                write!(f, "Digits {a} and {b} cannot be told apart")
+            }
+            DecodeError::WrongPatternCount { expected, found } => {
+                write!(f, "Expected {expected} signal patterns, found {found}")
+            }
+            DecodeError::UnmatchedPattern(pattern) => {
+                write!(f, "Signal pattern \"{pattern}\" does not fit any digit")
+            }
+            DecodeError::DuplicateDigit(digit) => {
+                write!(f, "More than one signal pattern fits digit {digit}")
+            }
+            DecodeError::InconsistentWiring => {
+                write!(f, "The signal patterns do not result from any wiring")
+            }
+            DecodeError::UnknownOutputPattern(pattern) => {
+                write!(f, "Output pattern \"{pattern}\" is not a signal pattern")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The segments lit for each digit of a display, the digit's value being its index.
+///
+/// Displays may have up to 26 segments, named by the letters starting at `a`, and up to 64 digits.
+#[derive(Debug, Clone)]
+pub struct SegmentTable {
+    num_segments: usize,
+    digit_patterns: Vec<Pattern>,
+    signatures: Vec<Signature>,
+    sorted_segment_memberships: Vec<u64>,
+}
+
+impl SegmentTable {
+    /// Creates a table from the segments of each digit, such as `"cf"` for a one.
+    ///
+    /// Fails if two digits cannot be told apart after the wires have been mixed up.
+    pub fn new(num_segments: usize, digit_segments: &[&str]) -> Result<Self, DecodeError> {
+        assert!(num_segments <= Pattern::CAPACITY, "too many segments");
+        assert!(
+            digit_segments.len() <= u64::BITS as usize,
+            "too many digits"
+        );
 
-            let mut output_value_patterns = [[false; 7]; 4];
-            for (output_value_pattern, pattern_str) in output_value_patterns
-                .iter_mut()
-                .zip(output_value_patterns_str.split(' '))
+        let digit_patterns: Vec<Pattern> = digit_segments
+            .iter()
+            .map(|segments| parse_pattern(segments))
+            .collect();
+        let signatures = signatures(&digit_patterns);
+        for (a, signature) in signatures.iter().enumerate() {
+            if let Some(b) = signatures[a + 1..]
+                .iter()
+                .position(|other| other == signature)
             {
-                for b in pattern_str.bytes() {
-                    output_value_pattern[(b - b'a') as usize] = true;
-                }
+                return Err(DecodeError::AmbiguousDigits(a, a + 1 + b));
             }
+        }
 
-            (signal_patterns, output_value_patterns)
+        Ok(Self {
+            num_segments,
+            sorted_segment_memberships: sorted_segment_memberships(num_segments, &digit_patterns),
+            digit_patterns,
+            signatures,
         })
-        .collect()
-}
+    }
 
-fn permute_pattern(pattern: [bool; 7], permutation: [usize; 7]) -> [bool; 7] {
-    let mut permuted_pattern = [false; 7];
+    /// The ordinary seven-segment display of the digits 0 to 9.
+    pub fn seven_segment() -> Self {
+        Self::new(
+            7,
+            &[
+                "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg",
+                "abcdfg",
+            ],
+        )
+        .expect("the digits of a seven-segment display can be told apart")
+    }
 
-    for (bit, target_index) in pattern.iter().copied().zip(permutation) {
-        permuted_pattern[target_index] = bit;
+    /// Whether exactly one digit lights the given number of segments, so that it can be
+    /// recognized without decoding the wiring.
+    pub fn has_unique_segment_count(&self, segment_count: usize) -> bool {
+        self.digit_patterns
+            .iter()
+            .filter(|pattern| pattern.len() == segment_count)
+            .count()
+            == 1
     }
 
-    permuted_pattern
+    /// Decodes the output patterns of an entry into digits, given the patterns of all digits in
+    /// unknown order and with unknown wiring.
+    ///
+    /// Each signal pattern is identified by its signature, which takes a fixed number of set
+    /// operations for a given table, instead of trying out all permutations of the wires.
+    pub fn decode(
+        &self,
+        signal_patterns: &[Pattern],
+        output_patterns: &[Pattern],
+    ) -> Result<Vec<usize>, DecodeError> {
+        if signal_patterns.len() != self.digit_patterns.len() {
+            return Err(DecodeError::WrongPatternCount {
+                expected: self.digit_patterns.len(),
+                found: signal_patterns.len(),
+            });
+        }
+
+        let mut digits_by_pattern = HashMap::with_capacity(signal_patterns.len());
+        let mut decoded_patterns = vec![None; self.digit_patterns.len()];
+        for (&pattern, signature) in signal_patterns.iter().zip(signatures(signal_patterns)) {
+            let digit = self
+                .signatures
+                .iter()
+                .position(|digit_signature| *digit_signature == signature)
+                .ok_or_else(|| DecodeError::UnmatchedPattern(pattern.to_string()))?;
+            if decoded_patterns[digit].replace(pattern).is_some() {
+                return Err(DecodeError::DuplicateDigit(digit));
+            }
+            digits_by_pattern.insert(pattern, digit);
+        }
+
+        // A rewiring exists iff the wires are used by the same sets of digits as the segments.
+        let decoded_patterns: Vec<Pattern> = decoded_patterns.into_iter().flatten().collect();
+        let uses_unknown_wire = signal_patterns
+            .iter()
+            .any(|&pattern| pattern.bits() >> self.num_segments != 0);
+        if uses_unknown_wire
+            || sorted_segment_memberships(self.num_segments, &decoded_patterns)
+                != self.sorted_segment_memberships
+        {
+            return Err(DecodeError::InconsistentWiring);
+        }
+
+        output_patterns
+            .iter()
+            .map(|pattern| {
+                digits_by_pattern
+                    .get(pattern)
+                    .copied()
+                    .ok_or_else(|| DecodeError::UnknownOutputPattern(pattern.to_string()))
+            })
+            .collect()
+    }
+}
+
+struct Entry {
+    signal_patterns: Vec<Pattern>,
+    output_patterns: Vec<Pattern>,
+}
+
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Vec<Entry> {
+    input_lines
+        .map(|line| {
+            let (signal_patterns_str, output_value_patterns_str) = line
+                .split_once(" | ")
+                .expect("expected separator between signal patterns and output values");
+
+            Entry {
+                signal_patterns: signal_patterns_str.split(' ').map(parse_pattern).collect(),
+                output_patterns: output_value_patterns_str
+                    .split(' ')
+                    .map(parse_pattern)
+                    .collect(),
+            }
+        })
+        .collect()
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
     let entries = parse_input(input_lines);
+    let table = SegmentTable::seven_segment();
 
     let solution: usize = entries
         .iter()
-        .map(|(_signal_patterns, output_value_patterns)| {
-            output_value_patterns
+        .map(|entry| {
+            entry
+                .output_patterns
                 .iter()
-                .map(|pattern| pattern.iter().filter(|&&b| b).count())
-                .filter(|num_set| [2, 3, 4, 7].contains(num_set))
+                .filter(|pattern| table.has_unique_segment_count(pattern.len()))
                 .count()
         })
         .sum();
@@ -66,49 +254,89 @@ pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let entries = parse_input(input_lines);
+    let table = SegmentTable::seven_segment();
 
-    let pattern_to_digit_map: HashMap<[bool; 7], u8> = [
-        ([true, true, true, false, true, true, true], 0),
-        ([false, false, true, false, false, true, false], 1),
-        ([true, false, true, true, true, false, true], 2),
-        ([true, false, true, true, false, true, true], 3),
-        ([false, true, true, true, false, true, false], 4),
-        ([true, true, false, true, false, true, true], 5),
-        ([true, true, false, true, true, true, true], 6),
-        ([true, false, true, false, false, true, false], 7),
-        ([true, true, true, true, true, true, true], 8),
-        ([true, true, true, true, false, true, true], 9),
-    ]
-    .iter()
-    .copied()
-    .collect();
-
-    let solution = entries
-        .iter()
-        .map(|(signal_patterns, output_value_patterns)| {
-            let permutation: [usize; 7] = (0..7)
-                .permutations(7)
-                .map(|permutation| <[usize; 7]>::try_from(permutation.as_slice()).unwrap())
-                .filter(|&permutation| {
-                    signal_patterns
-                        .iter()
-                        .copied()
-                        .map(|pattern| permute_pattern(pattern, permutation))
-                        .filter_map(|pattern| pattern_to_digit_map.get(&pattern))
-                        .count()
-                        == 10
-                })
-                .next()
-                .unwrap();
-
-            output_value_patterns
-                .iter()
-                .copied()
-                .map(|permuted_pattern| permute_pattern(permuted_pattern, permutation))
-                .map(|pattern| *pattern_to_digit_map.get(&pattern).unwrap())
-                .fold(0u16, |acc, digit| acc * 10 + digit as u16) as usize
-        })
-        .sum::<usize>();
+    let mut solution = 0;
+    let mut errors = Vec::new();
+    for (entry_index, entry) in entries.iter().enumerate() {
+        match table.decode(&entry.signal_patterns, &entry.output_patterns) {
+            Ok(digits) => solution += digits.iter().fold(0, |acc, &digit| acc * 10 + digit),
+            Err(err) => errors.push(format!("entry {}: {err}", entry_index + 1)),
+        }
+    }
 
-    solution.to_string()
+    if errors.is_empty() {
+        solution.to_string()
+    } else {
+        format!(
+            "{} entries could not be decoded\n{}",
+            errors.len(),
+            errors.join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_patterns(patterns_str: &str) -> Vec<Pattern> {
+        patterns_str.split(' ').map(parse_pattern).collect()
+    }
+
+    #[test]
+    fn test_decode_seven_segment() {
+        let table = SegmentTable::seven_segment();
+        assert_eq!(
+            table.decode(
+                &parse_patterns("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab"),
+                &parse_patterns("cdfeb fcadb cdfeb cdbaf"),
+            ),
+            Ok(vec![5, 3, 5, 3])
+        );
+        assert_eq!(
+            table.decode(
+                &parse_patterns("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab"),
+                &parse_patterns("cdfeb fcad"),
+            ),
+            Err(DecodeError::UnknownOutputPattern(String::from("acdf")))
+        );
+        assert!(matches!(
+            table.decode(
+                &parse_patterns("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb abc"),
+                &parse_patterns("cdfeb"),
+            ),
+            Err(DecodeError::UnmatchedPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_table() {
+        // A few characters of a fourteen-segment display.
+        let table = SegmentTable::new(
+            14,
+            &[
+                "abcdef", "bc", "abdeg", "abcdg", "bcfg", "efghk", "adjm", "hiln",
+            ],
+        )
+        .unwrap();
+        // The same patterns with the segments shifted by three.
+        let shift = |pattern: Pattern| {
+            let bits = pattern.bits();
+            Pattern::from_bits((bits << 3 | bits >> 11) & 0x3fff).unwrap()
+        };
+        let signal_patterns: Vec<Pattern> = [5, 3, 7, 0, 1, 6, 2, 4]
+            .iter()
+            .map(|&digit| shift(table.digit_patterns[digit]))
+            .collect();
+        assert_eq!(
+            table.decode(&signal_patterns, &[signal_patterns[2], signal_patterns[0]]),
+            Ok(vec![7, 5])
+        );
+
+        assert_eq!(
+            SegmentTable::new(3, &["ab", "bc"]).unwrap_err(),
+            DecodeError::AmbiguousDigits(0, 1)
+        );
+    }
 }
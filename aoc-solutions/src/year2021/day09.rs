@@ -2,6 +2,8 @@ use std::collections::HashSet;
 
 use ndarray::Array2;
 
+use crate::render::{self, Raster, Rgb};
+
 fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Array2<u8> {
     let mut width = None;
     let raw_heights: Vec<u8> = input_lines
@@ -42,22 +44,30 @@ fn neighbors(
     .filter_map(|neighboring_index| neighboring_index)
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let height_map = parse_input(input_lines);
-
-    let solution: usize = height_map
+fn low_points(height_map: &Array2<u8>) -> impl Iterator<Item = (usize, usize)> + '_ {
+    height_map
         .indexed_iter()
         .filter(|&(index, &height)| {
             neighbors(height_map.raw_dim(), index)
                 .all(|neighboring_index| height < height_map[neighboring_index])
         })
-        .map(|(_index, height)| (height + 1) as usize)
+        .map(|(index, _height)| index)
+}
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let height_map = parse_input(input_lines);
+
+    let solution: usize = low_points(&height_map)
+        .map(|low_point| (height_map[low_point] + 1) as usize)
         .sum::<usize>();
 
     solution.to_string()
 }
 
-fn basin_size_of_low_point(height_map: &Array2<u8>, low_point: (usize, usize)) -> usize {
+fn basin_of_low_point(
+    height_map: &Array2<u8>,
+    low_point: (usize, usize),
+) -> HashSet<(usize, usize)> {
     let mut to_process = HashSet::new();
     let mut in_basin = HashSet::new();
 
@@ -77,19 +87,14 @@ fn basin_size_of_low_point(height_map: &Array2<u8>, low_point: (usize, usize)) -
         to_process = new_to_process;
     }
 
-    in_basin.len()
+    in_basin
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let height_map = parse_input(input_lines);
 
-    let mut basins: Vec<usize> = height_map
-        .indexed_iter()
-        .filter(|&(index, &height)| {
-            neighbors(height_map.raw_dim(), index)
-                .all(|neighboring_index| height < height_map[neighboring_index])
-        })
-        .map(|(low_point, _height)| basin_size_of_low_point(&height_map, low_point))
+    let mut basins: Vec<usize> = low_points(&height_map)
+        .map(|low_point| basin_of_low_point(&height_map, low_point).len())
         .collect();
 
     basins.sort_unstable();
@@ -99,3 +104,48 @@ pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
         .product::<usize>()
         .to_string()
 }
+
+const LOW_POINT_COLOR: Rgb = [214, 39, 40];
+
+/// Draws the height map in shades of gray, from black for height 0 to white for height 9, with the
+/// low points in red.
+pub fn render_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    let height_map = parse_input(input_lines);
+
+    let mut raster = Raster::from_grid(height_map.view(), |&height| {
+        [(u16::from(height) * 255 / 9) as u8; 3]
+    });
+    for low_point in low_points(&height_map) {
+        raster.pixels_mut()[low_point] = LOW_POINT_COLOR;
+    }
+    raster
+}
+
+/// Draws each basin in its own color and the locations of height 9 separating them in black.
+pub fn render_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    let height_map = parse_input(input_lines);
+
+    let mut raster = Raster::from_grid(height_map.view(), |_| render::BLACK);
+    for (basin_index, low_point) in low_points(&height_map).enumerate() {
+        let color = render::PALETTE[basin_index % render::PALETTE.len()];
+        for point in basin_of_low_point(&height_map, low_point) {
+            raster.pixels_mut()[point] = color;
+        }
+    }
+    raster
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_height_map() {
+        let raster = render_puzzle1(["909", "959"].iter().map(|s| s.to_string()));
+        assert_eq!((raster.width(), raster.height()), (3, 2));
+        assert_eq!(raster.pixels()[(0, 0)], [255; 3]);
+        assert_eq!(raster.pixels()[(0, 1)], LOW_POINT_COLOR);
+        assert_eq!(raster.pixels()[(1, 0)], [255; 3]);
+        assert_eq!(raster.pixels()[(1, 1)], [141; 3]);
+    }
+}
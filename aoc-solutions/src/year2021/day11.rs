@@ -1,7 +1,7 @@
-use array_init;
+use ndarray::Array2;
 
-const WIDTH: usize = 10;
-const HEIGHT: usize = 10;
+use crate::automaton::{Automaton, Boundary, Moore};
+use crate::render::{Raster, Rgb};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum OctopusState {
@@ -59,104 +59,123 @@ impl TryFrom<u8> for OctopusState {
     }
 }
 
-fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> [[OctopusState; WIDTH]; HEIGHT] {
-    array_init::from_iter(input_lines.map(|line| {
-        array_init::from_iter(line.bytes().map(|b| OctopusState::try_from(b).unwrap())).unwrap()
-    }))
-    .unwrap()
-}
-
-fn for_neighbors<F>(array: &mut [[OctopusState; WIDTH]; HEIGHT], x: usize, y: usize, mut f: F)
-where
-    F: FnMut(&mut OctopusState),
-{
-    for neighbor_x in x.saturating_sub(1)..=x.saturating_add(1).min(WIDTH - 1) {
-        for neighbor_y in y.saturating_sub(1)..=y.saturating_add(1).min(HEIGHT - 1) {
-            if neighbor_x == x && neighbor_y == y {
-                continue;
+fn parse_input<I: Iterator<Item = String>>(input_lines: I) -> Array2<OctopusState> {
+    let mut width = None;
+    let raw_states: Vec<OctopusState> = input_lines
+        .flat_map(|line| {
+            if let Some(width) = width {
+                assert_eq!(line.len(), width);
+            } else {
+                width = Some(line.len());
             }
-            f(&mut array[neighbor_y][neighbor_x]);
-        }
-    }
-}
+            line.bytes()
+                .map(|b| OctopusState::try_from(b).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect();
 
-fn step_octopus_population(
-    mut octopus_states: [[OctopusState; WIDTH]; HEIGHT],
-) -> (usize, [[OctopusState; WIDTH]; HEIGHT]) {
-    let mut new_octopus_states = [[OctopusState::L0; WIDTH]; HEIGHT];
+    let width = width.unwrap();
 
-    for (new_octopus_state, &octopus_state) in new_octopus_states
-        .iter_mut()
-        .flatten()
-        .zip(octopus_states.iter().flatten())
-    {
-        *new_octopus_state = octopus_state.increase_energy();
-    }
+    Array2::from_shape_vec((raw_states.len() / width, width), raw_states).unwrap()
+}
 
-    octopus_states = new_octopus_states;
+type OctopusPopulation =
+    Automaton<OctopusState, Moore, fn(OctopusState, &[OctopusState]) -> OctopusState>;
 
-    loop {
-        for ((x, y), _octopus_state) in octopus_states
+/// Lets charged octopuses flash, passing one unit of energy on to each of their neighbors.
+fn flash_rule(octopus_state: OctopusState, neighbors: &[OctopusState]) -> OctopusState {
+    match octopus_state {
+        OctopusState::Charged | OctopusState::Flashed => OctopusState::Flashed,
+        _ => neighbors
             .iter()
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.into_iter()
-                    .enumerate()
-                    .map(move |(x, state)| ((x, y), state))
-            })
-            .filter(|&(_, octopus_state)| *octopus_state == OctopusState::Charged)
-        {
-            new_octopus_states[y][x] = OctopusState::Flashed;
-            for_neighbors(&mut new_octopus_states, x, y, |neighbor| {
-                *neighbor = neighbor.increase_energy();
-            });
-        }
+            .filter(|&&neighbor| neighbor == OctopusState::Charged)
+            .fold(octopus_state, |state, _| state.increase_energy()),
+    }
+}
 
-        if new_octopus_states == octopus_states {
-            break;
-        }
+fn octopus_population(octopus_states: Array2<OctopusState>) -> OctopusPopulation {
+    Automaton::new(
+        octopus_states,
+        Moore::default(),
+        flash_rule,
+        Boundary::Fixed(OctopusState::L0),
+    )
+}
 
-        octopus_states = new_octopus_states;
-    }
+/// Advances the population by one step and returns the number of octopuses that flashed.
+fn step_octopus_population(octopus_population: &mut OctopusPopulation) -> usize {
+    octopus_population
+        .cells_mut()
+        .map_inplace(|state| *state = state.increase_energy());
+
+    // Flashes propagate until no octopus is left charged.
+    octopus_population.run_until_stable();
 
-    let flashed_count = new_octopus_states
+    octopus_population
+        .cells_mut()
         .iter_mut()
-        .flatten()
         .filter(|state| **state == OctopusState::Flashed)
         .fold(0, |flashed_count, state| {
             *state = OctopusState::L0;
             flashed_count + 1
-        });
-
-    (flashed_count, new_octopus_states)
+        })
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let octopus_states = parse_input(input_lines);
+    let mut octopus_population = octopus_population(parse_input(input_lines));
 
-    let (flashed_count_total, _) = (0..100).fold(
-        (0, octopus_states),
-        |(flashed_count_total, octopus_states), _| {
-            let (flashed_count, new_states) = step_octopus_population(octopus_states);
-            (flashed_count_total + flashed_count, new_states)
-        },
-    );
+    let flashed_count_total: usize = (0..100)
+        .map(|_| step_octopus_population(&mut octopus_population))
+        .sum();
 
     flashed_count_total.to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let mut octopus_states = parse_input(input_lines);
+    let mut octopus_population = octopus_population(parse_input(input_lines));
+    let population_size = octopus_population.cells().len();
 
     let mut step_count = 0;
     let steps_until_synchronization = loop {
-        let (flashed_count, new_states) = step_octopus_population(octopus_states);
+        let flashed_count = step_octopus_population(&mut octopus_population);
         step_count += 1;
-        if flashed_count == WIDTH * HEIGHT {
+        if flashed_count == population_size {
             break step_count;
         }
-        octopus_states = new_states;
     };
 
     steps_until_synchronization.to_string()
 }
+
+/// Octopuses that have just flashed are drawn in white, the others in shades of blue getting
+/// brighter with their energy level.
+fn octopus_color(octopus_state: &OctopusState) -> Rgb {
+    match *octopus_state {
+        OctopusState::L0 => [255, 255, 255],
+        state => {
+            let energy = state as u8;
+            [0, 12 * energy, 40 + 20 * energy]
+        }
+    }
+}
+
+/// Yields the population before and after each step, until all octopuses flash at once.
+pub fn animation_frames<I: Iterator<Item = String>>(
+    input_lines: I,
+) -> impl Iterator<Item = Raster> {
+    let mut octopus_population = octopus_population(parse_input(input_lines));
+    let initial_frame = Raster::from_grid(octopus_population.cells().view(), octopus_color);
+
+    let mut synchronized = false;
+    std::iter::once(initial_frame).chain(std::iter::from_fn(move || {
+        if synchronized {
+            return None;
+        }
+        synchronized =
+            step_octopus_population(&mut octopus_population) == octopus_population.cells().len();
+        Some(Raster::from_grid(
+            octopus_population.cells().view(),
+            octopus_color,
+        ))
+    }))
+}
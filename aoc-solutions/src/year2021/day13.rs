@@ -1,3 +1,8 @@
+use ndarray::Array2;
+
+use crate::ocr;
+use crate::render::Raster;
+
 #[derive(Clone, Copy)]
 enum FoldingInstruction {
     AlongX(u16),
@@ -54,32 +59,51 @@ pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
     dots.len().to_string()
 }
 
-pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+/// Draws the given dots onto a sheet just large enough to hold all of them.
+fn dots_to_paper(dots: &[(u16, u16)]) -> Array2<bool> {
+    let (max_x, max_y) = dots.iter().fold((0, 0), |(max_x, max_y), &(dot_x, dot_y)| {
+        (max_x.max(dot_x as usize), max_y.max(dot_y as usize))
+    });
+
+    let mut paper = Array2::from_elem((max_y + 1, max_x + 1), false);
+    for &(dot_x, dot_y) in dots {
+        paper[(dot_y as usize, dot_x as usize)] = true;
+    }
+
+    paper
+}
+
+/// Folds the paper along all folding instructions and returns the dots that are visible.
+pub fn fold_paper<I: Iterator<Item = String>>(input_lines: I) -> Array2<bool> {
     let (mut dots, folding_instructions) = parse_input(input_lines);
 
     for folding_instruction in folding_instructions {
         fold(&mut dots, folding_instruction);
     }
 
-    let (max_x, max_y) = dots.iter().fold((0, 0), |(max_x, max_y), &(dot_x, dot_y)| {
-        (max_x.max(dot_x as usize), max_y.max(dot_y as usize))
-    });
+    dots_to_paper(&dots)
+}
 
-    let width = max_x + 1;
-    let height = max_y + 1;
+pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let paper = fold_paper(input_lines);
 
-    let mut paper = vec![false; width * height];
-    for (dot_x, dot_y) in dots {
-        paper[dot_x as usize + dot_y as usize * width] = true;
+    match ocr::recognize_text(&paper) {
+        Ok(code) => code,
+        // Fall back to the picture, so the code can still be read manually.
+        Err(err) => format!("{err}\n{}", ocr::render_picture(paper.view())),
     }
+}
 
-    let pattern: String = std::iter::once("\n")
-        .chain(paper.chunks(width).flat_map(|row| {
-            row.into_iter()
-                .map(|on| if *on { "#" } else { "." })
-                .chain(["\n"])
-        }))
-        .collect();
+/// Draws the paper after the first fold.
+pub fn render_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    let (mut dots, folding_instructions) = parse_input(input_lines);
+
+    fold(&mut dots, folding_instructions[0]);
+
+    Raster::from_bool_grid(dots_to_paper(&dots).view())
+}
 
-    pattern
+/// Draws the paper after all folds, showing the code.
+pub fn render_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    Raster::from_bool_grid(fold_paper(input_lines).view())
 }
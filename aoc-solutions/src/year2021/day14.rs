@@ -3,6 +3,8 @@ use std::{collections::HashMap, hash::Hash};
 use array_init;
 use itertools::{Itertools, MinMaxResult};
 
+use crate::linalg::{Arithmetic, Element, SquareMatrix};
+
 struct Counts<Item> {
     map: HashMap<Item, u64>,
 }
@@ -55,33 +57,94 @@ fn parse_input<I: Iterator<Item = String>>(
     (pair_counts, insertion_rules)
 }
 
-fn run_polymerization(
-    mut pair_counts: HashMap<[u8; 2], u64>,
-    insertion_rules: HashMap<[u8; 2], u8>,
-    steps: usize,
-) -> u64 {
-    let mut new_pair_counts: HashMap<[u8; 2], u64> = HashMap::new();
-
-    for _ in 0..steps {
-        for (&pair, &count) in pair_counts.iter() {
-            if let Some(&to_insert) = insertion_rules.get(&pair) {
-                *new_pair_counts.entry([pair[0], to_insert]).or_insert(0) += count;
-                *new_pair_counts.entry([to_insert, pair[1]]).or_insert(0) += count;
-            } else {
-                *new_pair_counts.entry(pair).or_insert(0) += count;
-            }
+/// The matrix mapping the counts of each of `pairs` to those after one insertion step.
+fn polymerization_matrix<T: Element>(
+    pairs: &[[u8; 2]],
+    pair_indices: &HashMap<[u8; 2], usize>,
+    insertion_rules: &HashMap<[u8; 2], u8>,
+) -> SquareMatrix<T> {
+    let mut matrix = SquareMatrix::<T>::zero(pairs.len());
+    for (from, pair) in pairs.iter().enumerate() {
+        let new_pairs = match insertion_rules.get(pair) {
+            Some(&to_insert) => vec![[pair[0], to_insert], [to_insert, pair[1]]],
+            None => vec![*pair],
+        };
+        for new_pair in new_pairs {
+            let to = pair_indices[&new_pair];
+            matrix[(to, from)] = matrix[(to, from)].checked_add(T::ONE).unwrap();
         }
-        pair_counts.clear();
-        pair_counts.extend(new_pair_counts.drain());
-        new_pair_counts.clear();
     }
+    matrix
+}
 
-    let counts = pair_counts
+fn count_elements_after_steps<T: Element + From<u64>>(
+    pair_counts: HashMap<[u8; 2], u64>,
+    insertion_rules: HashMap<[u8; 2], u8>,
+    steps: u64,
+    arithmetic: Arithmetic<T>,
+) -> Option<HashMap<u8, T>> {
+    let pairs: Vec<[u8; 2]> = pair_counts
+        .keys()
+        .copied()
+        .chain(
+            insertion_rules
+                .iter()
+                .flat_map(|(&pair, &to_insert)| [pair, [pair[0], to_insert], [to_insert, pair[1]]]),
+        )
+        .sorted_unstable()
+        .dedup()
+        .collect();
+    let pair_indices: HashMap<[u8; 2], usize> = pairs
         .iter()
-        .fold(HashMap::new(), |mut counts, (&[element, _], &count)| {
-            *counts.entry(element).or_insert(0) += count;
-            counts
-        });
+        .enumerate()
+        .map(|(i, &pair)| (pair, i))
+        .collect();
+
+    let initial_counts: Vec<T> = pairs
+        .iter()
+        .map(|pair| T::from(pair_counts.get(pair).copied().unwrap_or(0)))
+        .collect();
+    let final_counts = polymerization_matrix(&pairs, &pair_indices, &insertion_rules)
+        .pow(steps, arithmetic)?
+        .mul_vector(&initial_counts, arithmetic)?;
+
+    // Every element is the first of exactly one pair, as the last one is paired with a 0.
+    pairs
+        .iter()
+        .zip(final_counts)
+        .try_fold(HashMap::new(), |mut counts, (&[element, _], count)| {
+            let element_count = counts.entry(element).or_insert(T::ZERO);
+            *element_count = arithmetic.add(*element_count, count)?;
+            Some(counts)
+        })
+}
+
+/// Counts each element of the polymer after `steps` insertion steps, in logarithmic time in
+/// `steps`.
+///
+/// Returns `None` if a count overflows with [`Arithmetic::Checked`].
+pub fn count_elements<T, I>(
+    input_lines: I,
+    steps: u64,
+    arithmetic: Arithmetic<T>,
+) -> Option<HashMap<u8, T>>
+where
+    T: Element + From<u64>,
+    I: Iterator<Item = String>,
+{
+    let (pair_counts, insertion_rules) = parse_input(input_lines);
+
+    count_elements_after_steps(pair_counts, insertion_rules, steps, arithmetic)
+}
+
+fn run_polymerization(
+    pair_counts: HashMap<[u8; 2], u64>,
+    insertion_rules: HashMap<[u8; 2], u8>,
+    steps: u64,
+) -> u64 {
+    let counts =
+        count_elements_after_steps::<u64>(pair_counts, insertion_rules, steps, Arithmetic::Checked)
+            .expect("element count overflowed");
 
     let solution = match counts.values().minmax() {
         MinMaxResult::NoElements => 0,
@@ -107,3 +170,62 @@ pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
 
     solution.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    const INPUT: &str = indoc! {"
+        NNCB
+
+        CH -> B
+        HH -> N
+        CB -> H
+        NH -> C
+        HB -> C
+        HC -> B
+        HN -> C
+        NN -> C
+        BH -> H
+        NC -> B
+        NB -> B
+        BN -> B
+        BB -> N
+        BC -> B
+        CC -> N
+        CN -> C
+    "};
+
+    fn example_input() -> impl Iterator<Item = String> {
+        INPUT.lines().map(String::from)
+    }
+
+    #[test]
+    fn test_example_counts() {
+        assert_eq!(solve_puzzle1(example_input()), "1588");
+        assert_eq!(solve_puzzle2(example_input()), "2188189693529");
+
+        let counts = count_elements::<u64, _>(example_input(), 40, Arithmetic::Checked).unwrap();
+        assert_eq!(counts[&b'B'], 2192039569602);
+        assert_eq!(counts[&b'H'], 3849876073);
+    }
+
+    #[test]
+    fn test_huge_number_of_steps() {
+        const STEPS: u64 = 1_000_000_000_000_000_000;
+        assert_eq!(
+            count_elements::<u128, _>(example_input(), STEPS, Arithmetic::Checked),
+            None
+        );
+
+        let counts =
+            count_elements::<u64, _>(example_input(), STEPS, Arithmetic::Modular(1_000_000_007))
+                .unwrap();
+        assert_eq!(
+            [b'B', b'C', b'H', b'N'].map(|element| counts[&element]),
+            [411597790, 201680736, 681730171, 863420084]
+        );
+    }
+}
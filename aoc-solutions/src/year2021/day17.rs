@@ -1,5 +1,10 @@
-#[derive(Clone, Copy)]
-struct Target {
+use ndarray::{s, Array2};
+
+use crate::render::{Raster, Rgb};
+
+/// The target area, given by its corners with the smallest and largest coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
     pub min: [i16; 2],
     pub max: [i16; 2],
 }
@@ -56,53 +61,314 @@ impl Probe {
             && self.pos[0] <= target.max[0]
             && self.pos[1] <= target.max[1]
     }
+}
+
+/// The distance covered by a velocity of `n` that drops by one with each step until it reaches 0.
+fn triangular(n: i64) -> i64 {
+    n * (n + 1) / 2
+}
+
+fn div_ceil(a: i64, b: i64) -> i64 {
+    -(-a).div_euclid(b)
+}
+
+/// The non-negative x velocities that bring the probe to a halt within `min..=max`, so that it
+/// stays there forever, in increasing order.
+fn halting_x_velocities(min: i64, max: i64) -> Vec<i64> {
+    (0..)
+        .take_while(|&velocity| triangular(velocity) <= max)
+        .filter(|&velocity| triangular(velocity) >= min)
+        .collect()
+}
+
+/// The non-negative x velocities for which the probe is within `min..=max` after `step` steps,
+/// given `0 <= min` and the `halting` velocities of the range.
+///
+/// Velocities of at least `step - 1` are still moving the probe, which is then at
+/// `step * velocity - triangular(step - 1)`. Smaller ones have already come to a halt at
+/// `triangular(velocity)`.
+fn x_velocities_at_step(
+    min: i64,
+    max: i64,
+    halting: &[i64],
+    step: i64,
+) -> impl Iterator<Item = i64> + '_ {
+    let moving = (step - 1).max(div_ceil(min + triangular(step - 1), step))
+        ..=(max + triangular(step - 1)).div_euclid(step);
+    let halted = halting
+        .iter()
+        .copied()
+        .take_while(move |&velocity| velocity < step - 1);
+    moving.chain(halted)
+}
+
+/// All initial velocities with which the probe hits a target, found analytically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrickShots {
+    velocities: Vec<[i16; 2]>,
+}
+
+impl TrickShots {
+    /// Finds all initial velocities for which the probe is within the target after some number of
+    /// steps.
+    ///
+    /// The y coordinate after `step` steps is `step * vy - triangular(step - 1)`, so for each
+    /// number of steps the hitting y velocities form an interval, and so do the x velocities that
+    /// are still moving. Only finitely many numbers of steps need to be considered, except when
+    /// the target includes the launch height and the probe can also halt within its x range. Then
+    /// infinitely many velocities hit it and an error is returned.
+    pub fn find(target: &Target) -> Result<Self, &'static str> {
+        let [min_x, min_y] = target.min.map(i64::from);
+        let [max_x, max_y] = target.max.map(i64::from);
+
+        // Velocities towards negative x mirror those towards positive x.
+        let x_ranges: Vec<(i64, i64, i64, Vec<i64>)> = [
+            (max_x >= 0).then(|| (min_x.max(0), max_x, 1)),
+            (min_x <= 0).then(|| ((-max_x).max(0), -min_x, -1)),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(min, max, sign)| (min, max, sign, halting_x_velocities(min, max)))
+        .collect();
+
+        let max_steps_x = if x_ranges.iter().any(|(.., halting)| !halting.is_empty()) {
+            None
+        } else {
+            // After `step` steps, a moving probe has covered at least `triangular(step - 1)`.
+            Some(min_x.abs().max(max_x.abs()) + 1)
+        };
+        let max_steps_y = if max_y < 0 {
+            // The probe returns to the launch height with the negated velocity minus one.
+            Some(-2 * min_y)
+        } else if min_y > 0 {
+            // The probe is above the launch height for at most `2 * vy` steps, and the first step
+            // overshoots for `vy > max_y`.
+            Some(2 * max_y)
+        } else {
+            None
+        };
+        let max_steps = match (max_steps_x, max_steps_y) {
+            (Some(max_steps_x), Some(max_steps_y)) => max_steps_x.min(max_steps_y),
+            (Some(max_steps), None) | (None, Some(max_steps)) => max_steps,
+            // The probe can halt within the target's x range and then pass through it again at the
+            // launch height after arbitrarily many steps.
+            (None, None) => return Err("infinitely many velocities hit the target"),
+        };
+
+        let mut velocities = Vec::new();
+        for step in 1..=max_steps {
+            let y_velocities = div_ceil(min_y + triangular(step - 1), step)
+                ..=(max_y + triangular(step - 1)).div_euclid(step);
+            if y_velocities.is_empty() {
+                continue;
+            }
+            for &(min, max, sign, ref halting) in &x_ranges {
+                for vx in x_velocities_at_step(min, max, halting, step) {
+                    velocities.extend(
+                        y_velocities
+                            .clone()
+                            .map(|vy| [(sign * vx) as i16, vy as i16]),
+                    );
+                }
+            }
+        }
+        velocities.sort_unstable();
+        velocities.dedup();
+
+        Ok(Self { velocities })
+    }
+
+    /// The hitting initial velocities, sorted by x and then y velocity.
+    pub fn velocities(&self) -> &[[i16; 2]] {
+        &self.velocities
+    }
 
-    pub fn beyond_target(&self, target: &Target) -> bool {
-        self.pos[1] < target.min[1]
+    pub fn count(&self) -> usize {
+        self.velocities.len()
     }
+
+    /// The highest y coordinate reached on any of the hitting trajectories, if there are any.
+    pub fn max_height(&self) -> Option<i64> {
+        self.velocities
+            .iter()
+            .map(|&velocity| apex_height(velocity))
+            .max()
+    }
+}
+
+/// The highest y coordinate reached on the trajectory of the given initial velocity.
+pub fn apex_height([_, vy]: [i16; 2]) -> i64 {
+    triangular(i64::from(vy).max(0))
 }
 
 pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
     let target = parse_input(input_lines);
 
-    (0..=target.max[0])
-        .flat_map(|x| {
-            (target.min[1]..=200).filter_map(move |y| {
-                let mut probe = Probe::from_initial_velocity([x, y]);
-                let mut max_height = 0;
-                loop {
-                    if probe.in_target(&target) {
-                        break Some(max_height);
-                    } else if probe.beyond_target(&target) {
-                        break None;
-                    }
-                    probe.step();
-                    max_height = max_height.max(probe.pos()[1]);
-                }
-            })
-        })
-        .max()
+    TrickShots::find(&target)
         .unwrap()
+        .max_height()
+        .expect("no initial velocity hits the target")
         .to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
     let target = parse_input(input_lines);
 
-    (0..=target.max[0])
-        .flat_map(|x| {
-            (target.min[1]..=200).filter_map(move |y| {
-                let mut probe = Probe::from_initial_velocity([x, y]);
-                loop {
-                    if probe.in_target(&target) {
-                        break Some([x, y]);
-                    } else if probe.beyond_target(&target) {
-                        break None;
-                    }
+    TrickShots::find(&target).unwrap().count().to_string()
+}
+
+/// The maximum width and height of animation frames in pixels.
+const CANVAS_SIZE: i32 = 80;
+
+const SKY_COLOR: Rgb = [16, 16, 32];
+const TARGET_COLOR: Rgb = [44, 160, 44];
+const PATH_COLOR: Rgb = [31, 119, 180];
+const PROBE_COLOR: Rgb = [214, 39, 40];
+
+/// Yields the position of the probe launched on the highest trajectory after each step, until it
+/// reaches the target area.
+///
+/// Each axis is scaled down separately to fit onto a canvas of [`CANVAS_SIZE`] pixels, as the
+/// highest trajectories are far taller than wide, so one pixel may cover several positions.
+pub fn animation_frames<I: Iterator<Item = String>>(
+    input_lines: I,
+) -> impl Iterator<Item = Raster> {
+    let target = parse_input(input_lines);
+    let velocity = TrickShots::find(&target)
+        .unwrap()
+        .velocities()
+        .iter()
+        .copied()
+        .max_by_key(|&velocity| apex_height(velocity))
+        .expect("no initial velocity hits the target");
+
+    let mut probe = Probe::from_initial_velocity(velocity);
+    let mut trajectory = vec![probe.pos()];
+    while !probe.in_target(&target) {
+        probe.step();
+        trajectory.push(probe.pos());
+    }
+
+    let corners = trajectory
+        .iter()
+        .chain([&target.min, &target.max])
+        .map(|&[x, y]| [x as i32, y as i32]);
+    let (min, max) = corners.fold(([0; 2], [0; 2]), |(min, max), [x, y]| {
+        (
+            [min[0].min(x), min[1].min(y)],
+            [max[0].max(x), max[1].max(y)],
+        )
+    });
+    let span = [max[0] - min[0] + 1, max[1] - min[1] + 1];
+    let scale = span.map(|extent| (extent + CANVAS_SIZE - 1) / CANVAS_SIZE);
+
+    // Rows run from the top down, so higher positions end up in lower rows.
+    let to_pixel = move |[x, y]: [i16; 2]| {
+        (
+            ((max[1] - y as i32) / scale[1]) as usize,
+            ((x as i32 - min[0]) / scale[0]) as usize,
+        )
+    };
+
+    let mut background = Raster::from_pixels(Array2::from_elem(
+        (
+            ((span[1] + scale[1] - 1) / scale[1]) as usize,
+            ((span[0] + scale[0] - 1) / scale[0]) as usize,
+        ),
+        SKY_COLOR,
+    ));
+    let (top, left) = to_pixel([target.min[0], target.max[1]]);
+    let (bottom, right) = to_pixel([target.max[0], target.min[1]]);
+    background
+        .pixels_mut()
+        .slice_mut(s![top..=bottom, left..=right])
+        .fill(TARGET_COLOR);
+
+    (0..trajectory.len()).map(move |step| {
+        let mut frame = background.clone();
+        for &pos in &trajectory[..step] {
+            frame.pixels_mut()[to_pixel(pos)] = PATH_COLOR;
+        }
+        frame.pixels_mut()[to_pixel(trajectory[step])] = PROBE_COLOR;
+        frame
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulates all velocities within generous bounds for at most `max_steps` steps.
+    fn brute_force(target: &Target, max_steps: usize) -> Vec<[i16; 2]> {
+        let bound = 2 * target
+            .min
+            .iter()
+            .chain(&target.max)
+            .map(|c| c.abs())
+            .max()
+            .unwrap()
+            + 2;
+        let mut velocities = Vec::new();
+        for vx in -bound..=bound {
+            for vy in -bound..=bound {
+                let mut probe = Probe::from_initial_velocity([vx, vy]);
+                for _ in 0..max_steps {
                     probe.step();
+                    if probe.in_target(target) {
+                        velocities.push([vx, vy]);
+                        break;
+                    }
                 }
-            })
+            }
+        }
+        velocities
+    }
+
+    #[test]
+    fn test_example() {
+        let trick_shots = TrickShots::find(&Target {
+            min: [20, -10],
+            max: [30, -5],
         })
-        .count()
-        .to_string()
+        .unwrap();
+        assert_eq!(trick_shots.count(), 112);
+        assert_eq!(trick_shots.max_height(), Some(45));
+    }
+
+    #[test]
+    fn test_unusual_targets() {
+        for target in [
+            // Above the launcher.
+            Target {
+                min: [20, 5],
+                max: [30, 10],
+            },
+            // Behind the launcher.
+            Target {
+                min: [-30, -10],
+                max: [-20, -5],
+            },
+            // Straight below the launcher.
+            Target {
+                min: [-3, -10],
+                max: [4, -5],
+            },
+            // At the launch height, out of reach of halted probes.
+            Target {
+                min: [4, -3],
+                max: [5, 2],
+            },
+        ] {
+            assert_eq!(
+                TrickShots::find(&target).unwrap().velocities(),
+                brute_force(&target, 100),
+                "{target:?}"
+            );
+        }
+        assert!(TrickShots::find(&Target {
+            min: [5, -2],
+            max: [7, 2]
+        })
+        .is_err());
+    }
 }
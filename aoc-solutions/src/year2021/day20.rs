@@ -1,46 +1,63 @@
 use ndarray::Array2;
 
+use crate::automaton::{Automaton, Boundary, Moore, Rule};
+use crate::render::Raster;
+
+/// Looks up the new state of a pixel by the 3x3 window around it, read as a 9-bit number.
+#[derive(Clone)]
+struct EnhancementRule([bool; 512]);
+
+impl Rule<bool> for EnhancementRule {
+    fn apply(&self, _pixel_lit: bool, window: &[bool]) -> bool {
+        let lookup_index = window.iter().fold(0, |lookup_index, pixel_lit| {
+            lookup_index << 1 | *pixel_lit as usize
+        });
+        self.0[lookup_index]
+    }
+}
+
 struct Image {
-    default_lit: bool,
-    pixels: Array2<bool>,
+    automaton: Automaton<bool, Moore, EnhancementRule>,
 }
 
 impl Image {
-    pub fn enhance(&self, algorithm: [bool; 512]) -> Self {
-        let mut padded_pixels = Array2::from_elem(
-            (self.pixels.shape()[0] + 4, self.pixels.shape()[1] + 4),
-            self.default_lit,
-        );
-        padded_pixels
-            .slice_mut(ndarray::s![
-                2..padded_pixels.shape()[0] - 2,
-                2..padded_pixels.shape()[1] - 2
-            ])
-            .assign(&self.pixels);
-        let new_pixels = ndarray::Zip::from(padded_pixels.windows((3, 3))).map_collect(|window| {
-            let lookup_index = window.iter().fold(0, |lookup_index, pixel_lit| {
-                lookup_index << 1 | *pixel_lit as usize
-            });
-            algorithm[lookup_index]
-        });
-
-        Image {
-            default_lit: (!self.default_lit && algorithm[0b_000_000_000])
-                || (self.default_lit && algorithm[0b_111_111_111]),
-            pixels: new_pixels,
+    pub fn new(algorithm: [bool; 512], pixels: Array2<bool>) -> Self {
+        Self {
+            automaton: Automaton::new(
+                pixels,
+                Moore {
+                    include_center: true,
+                },
+                EnhancementRule(algorithm),
+                Boundary::Infinite(false),
+            ),
         }
     }
 
+    /// Makes room for `steps` further enhancements, each of which grows the image by one pixel on
+    /// every side.
+    pub fn reserve(&mut self, steps: usize) {
+        self.automaton.pad(steps);
+    }
+
+    pub fn enhance(&mut self) {
+        self.automaton.step();
+    }
+
     pub fn count_lit(&self) -> Option<usize> {
-        if !self.default_lit {
-            Some(self.pixels.iter().filter(|lit| **lit).count())
+        if !self.automaton.boundary().state() {
+            Some(self.automaton.cells().iter().filter(|lit| **lit).count())
         } else {
             None
         }
     }
+
+    pub fn pixels(&self) -> &Array2<bool> {
+        self.automaton.cells()
+    }
 }
 
-fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> ([bool; 512], Image) {
+fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> Image {
     let image_enhancement_algo =
         array_init::from_iter(input_lines.next().unwrap().bytes().map(|b| b == b'#')).unwrap();
 
@@ -64,31 +81,56 @@ fn parse_input<I: Iterator<Item = String>>(mut input_lines: I) -> ([bool; 512],
 
     let columns = columns.expect("expected non-empty input");
 
-    (
+    Image::new(
         image_enhancement_algo,
-        Image {
-            default_lit: false,
-            pixels: Array2::from_shape_vec((pixels.len() / columns, columns), pixels).unwrap(),
-        },
+        Array2::from_shape_vec((pixels.len() / columns, columns), pixels).unwrap(),
     )
 }
 
-pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let (image_enhancement_algo, mut image) = parse_input(input_lines);
+fn enhanced_image<I: Iterator<Item = String>>(input_lines: I, steps: usize) -> Image {
+    let mut image = parse_input(input_lines);
 
-    for _ in 0..2 {
-        image = image.enhance(image_enhancement_algo);
+    image.reserve(steps);
+    for _ in 0..steps {
+        image.enhance();
     }
 
+    image
+}
+
+pub fn solve_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> String {
+    let image = enhanced_image(input_lines, 2);
+
     image.count_lit().unwrap().to_string()
 }
 
 pub fn solve_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> String {
-    let (image_enhancement_algo, mut image) = parse_input(input_lines);
-
-    for _ in 0..50 {
-        image = image.enhance(image_enhancement_algo);
-    }
+    let image = enhanced_image(input_lines, 50);
 
     image.count_lit().unwrap().to_string()
 }
+
+/// Draws the image after two enhancements, lit pixels in black.
+pub fn render_puzzle1<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    Raster::from_bool_grid(enhanced_image(input_lines, 2).pixels().view())
+}
+
+/// Draws the image after 50 enhancements, lit pixels in black.
+pub fn render_puzzle2<I: Iterator<Item = String>>(input_lines: I) -> Raster {
+    Raster::from_bool_grid(enhanced_image(input_lines, 50).pixels().view())
+}
+
+/// Yields the image before and after each of 50 enhancements, lit pixels in black.
+pub fn animation_frames<I: Iterator<Item = String>>(
+    input_lines: I,
+) -> impl Iterator<Item = Raster> {
+    let mut image = parse_input(input_lines);
+    image.reserve(50);
+
+    (0..=50).map(move |step| {
+        if step > 0 {
+            image.enhance();
+        }
+        Raster::from_bool_grid(image.pixels().view())
+    })
+}
@@ -1,11 +1,16 @@
 #![feature(bool_to_option, once_cell)]
 
 use std::fs;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
-use clap::{crate_authors, crate_version, Parser};
+use clap::{crate_authors, crate_version, AppSettings, Parser, Subcommand};
 
+use aoc_solutions::params::{Parameter, Parameters};
+use aoc_solutions::render::ImageFormat;
+use aoc_solutions::report::ReportFormat;
 use aoc_solutions::*;
 
 fn open_input_reader<P: AsRef<Path>>(path_or_dash: P) -> io::Result<Box<dyn BufRead>> {
@@ -29,37 +34,142 @@ fn is_valid_day(v: &str) -> Result<(), String> {
         .ok_or("Not a valid day number (between 1 and 25)".to_owned())
 }
 
+fn is_valid_frame_rate(v: &str) -> Result<(), String> {
+    v.parse::<u32>()
+        .ok()
+        .and_then(|fps| (fps > 0).then_some(()))
+        .ok_or("Not a valid frame rate (a positive number of frames per second)".to_owned())
+}
+
 #[derive(Debug, Parser)]
 #[clap(version = crate_version!(), author = crate_authors!())]
+#[clap(setting = AppSettings::SubcommandsNegateReqs)]
 struct Options {
     /// Path to the file containing the input or '-' for stdin.
-    #[clap(short, long, parse(from_os_str), default_value = "-")]
+    #[clap(short, long, parse(from_os_str), default_value = "-", global = true)]
     input: PathBuf,
+    /// Parameter in the form name=value that changes what the puzzle asks, e.g. slopes=3/1,1/2
+    /// for 2020 day 3 (can be given multiple times)
+    #[clap(
+        short,
+        long = "param",
+        value_name = "NAME=VALUE",
+        global = true,
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    params: Vec<Parameter>,
+    #[clap(subcommand)]
+    command: Option<Command>,
     /// Year from which the puzzles should be selected
-    #[clap(possible_values = &["2020", "2021"])]
-    year: usize,
+    #[clap(possible_values = &["2020", "2021"], required = true)]
+    year: Option<usize>,
     /// Number of the day the puzzles of which should be used
-    #[clap(validator(is_valid_day))]
-    day: usize,
+    #[clap(validator(is_valid_day), required = true)]
+    day: Option<usize>,
     /// Number of the puzzle which should be used from the given day
-    #[clap(possible_values = &["1", "2"])]
-    puzzle_number: usize,
+    #[clap(possible_values = &["1", "2"], required = true)]
+    puzzle_number: Option<usize>,
+    /// Path of an image file to which the final state of the puzzle should be rendered, in the
+    /// format given by its extension (pbm, pgm, ppm, png or svg)
+    #[clap(long, parse(from_os_str))]
+    render: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Replays the simulation of a day step by step in the terminal
+    Animate {
+        /// Year from which the day should be selected
+        #[clap(possible_values = &["2020", "2021"])]
+        year: usize,
+        /// Number of the day the simulation of which should be replayed
+        #[clap(validator(is_valid_day))]
+        day: usize,
+        /// Number of frames shown per second
+        #[clap(long, default_value = "10", validator(is_valid_frame_rate))]
+        fps: u32,
+    },
+    /// Explains what the puzzles of a day make of the input, e.g. why passports are invalid or
+    /// how bags are nested
+    Report {
+        /// Year from which the day should be selected
+        #[clap(possible_values = &["2020", "2021"])]
+        year: usize,
+        /// Number of the day the report of which should be printed
+        #[clap(validator(is_valid_day))]
+        day: usize,
+        /// Format of the report
+        #[clap(long, default_value = "text", possible_values = &["text", "json", "dot"])]
+        format: ReportFormat,
+    },
+}
+
+fn play_animation<I: Iterator<Item = render::Raster>>(frames: I, fps: u32) {
+    let frame_duration = Duration::from_secs(1) / fps;
+    let mut stdout = io::stdout();
+
+    // Clear the screen once, then draw every frame over the previous one.
+    print!("\x1b[2J");
+    for (frame_number, frame) in frames.enumerate() {
+        println!("\x1b[H{}frame {frame_number}", frame.to_ansi());
+        stdout.flush().expect("Failed to write frame to stdout");
+        thread::sleep(frame_duration);
+    }
 }
 
 fn main() {
     let Options {
         input,
+        params,
+        command,
         year,
         day,
         puzzle_number,
+        render,
     } = Options::parse();
 
     let input_reader = open_input_reader(input).expect("Failed to open input file for reading");
-    let input_lines = input_reader
+    let input_lines: Vec<String> = input_reader
         .lines()
-        .map(|l| l.expect("Error occurred while reading lines from input"));
+        .map(|l| l.expect("Error occurred while reading lines from input"))
+        .collect();
+    let params: Parameters = params.into_iter().collect();
+
+    match command {
+        Some(Command::Animate { year, day, fps }) => {
+            let frames = animation_frames(year, day, input_lines.into_iter())
+                .unwrap_or_else(|| panic!("There is no simulation to animate for day {day}"));
+            play_animation(frames, fps);
+            return;
+        }
+        Some(Command::Report { year, day, format }) => {
+            let report = puzzle_report(year, day, input_lines.into_iter(), format, &params)
+                .unwrap_or_else(|| panic!("There is no report in this format for day {day}"));
+            println!("{}", report.trim_end());
+            return;
+        }
+        None => (),
+    }
+
+    // Without a subcommand, clap ensures that these are present.
+    let (year, day, puzzle_number) = (year.unwrap(), day.unwrap(), puzzle_number.unwrap());
+
+    if let Some(image_path) = render {
+        if ImageFormat::from_path(&image_path).is_none() {
+            panic!("Unsupported image format! Must be one of pbm, pgm, ppm, png or svg");
+        }
+        let raster = render_puzzle(year, day, puzzle_number, input_lines.iter().cloned())
+            .unwrap_or_else(|| {
+                panic!("Rendering is not supported for puzzle {puzzle_number} of day {day}")
+            });
+        raster
+            .save(&image_path)
+            .expect("Failed to write the rendered image");
+    }
 
-    let solution = solve_puzzle(year, day, puzzle_number, input_lines);
+    let solution =
+        solve_puzzle_with_params(year, day, puzzle_number, input_lines.into_iter(), &params);
 
     println!("The solution to puzzle {puzzle_number} of day {day} is \"{solution}\"")
 }